@@ -0,0 +1,55 @@
+extern crate assimp;
+
+use std::env;
+use std::process;
+
+use assimp as ai;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        println!("usage: {} <model> [output directory]", args[0]);
+        process::exit(1);
+    }
+    let model = &args[1];
+    let out_dir = args.get(2).map(|s| &s[..]).unwrap_or("textures");
+
+    let importer = ai::Importer::new();
+    let scene = match importer.import_from_file(model) {
+        Ok(scene) => scene,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    let extracted = match scene.extract_textures(out_dir) {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            println!("failed to extract textures: {}", e);
+            process::exit(1);
+        }
+    };
+
+    println!("{} embedded textures", extracted.files.len());
+    for (i, file) in extracted.files.iter().enumerate() {
+        if let Some(ref file) = *file {
+            println!("  *{} -> {}", i, file.display());
+        }
+    }
+    for &(i, ref e) in extracted.failures.iter() {
+        println!("  *{} failed: {}", i, e);
+    }
+
+    let materials = scene.get_materials();
+    for reference in extracted.references.iter() {
+        let name = materials[reference.material].get_name()
+                                                .unwrap_or_default();
+        println!("material {} ({:?}): {:?} #{} -> {}",
+                 reference.material,
+                 name,
+                 reference.tex_type,
+                 reference.index,
+                 reference.path.display());
+    }
+}
@@ -1,14 +1,285 @@
+//! Defines the scene exporter
 
-struct Exporter {
-    dummy: bool,
+use std::fmt;
+use std::str;
+use std::error::Error;
+use std::ffi::CString;
+use std::marker::PhantomData;
+
+use scene::Scene;
+use info;
+use postprocess::Process;
+use types::Return;
+use util::ptr_to_slice;
+use ffi;
+use ffi::RawExportDataBlob;
+
+/// The reasons an export can fail.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ExportError {
+    /// The file name contains an interior nul byte.
+    InvalidPath(String),
+
+    /// No exporter with the given format id is available in this build of
+    /// assimp.
+    UnsupportedFormat(String),
+
+    /// Assimp was unable to export the scene.
+    ExportFailed(String),
+
+    /// Not enough memory was available to export the scene.
+    OutOfMemory,
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExportError::InvalidPath(ref msg) =>
+                write!(f, "export error (invalid path): {}", msg),
+            ExportError::UnsupportedFormat(ref id) =>
+                write!(f, "export error (unsupported format): {}", id),
+            ExportError::ExportFailed(ref msg) =>
+                write!(f, "export error (export failed): {}", msg),
+            ExportError::OutOfMemory =>
+                write!(f, "export error: out of memory"),
+        }
+    }
+}
+
+impl Error for ExportError {}
+
+/// A scene exporter.
+///
+/// Scenes are written using the format ids known to assimp, for example
+/// `"collada"`, `"obj"`, `"stl"` or `"ply"`. Use
+/// `info::get_export_formats` to list the formats available.
+pub struct Exporter {
+    flags: u32,
 }
 
 impl Exporter {
-    fn new() -> Exporter  {
+    /// Create a new scene exporter
+    pub fn new() -> Exporter {
         Exporter {
-            dummy: false,
+            flags: 0,
+        }
+    }
+
+    /// Add pre processing steps to this exporter.
+    ///
+    /// Only a subset of the steps makes sense before exporting. The
+    /// `Process::MakeLeftHanded`, `Process::FlipUVs` and
+    /// `Process::FlipWindingOrder` steps can be used to convert a scene
+    /// which does not follow assimp's conventions back to them.
+    ///
+    /// See `Process` for a list of possible values.
+    pub fn add_processing_steps(&mut self, set: &[Process]) {
+        self.flags = set.iter().fold(self.flags, |x, &y| x | y as u32);
+    }
+
+    /// Removes the given pre processing steps from this exporter.
+    ///
+    /// See `Process` for a list of possible values.
+    pub fn rm_processing_steps(&mut self, set: &[Process]) {
+        self.flags = set.iter().fold(self.flags, |x, &y| x & !(y as u32));
+    }
+
+    /// Perform no pre processing steps
+    pub fn no_pre_processing(&mut self) {
+        self.flags = 0;
+    }
+
+    /// Write `scene` to `file_name` using the format with the id
+    /// `format_id`.
+    ///
+    /// Exporters which produce several files (e.g. OBJ and its MTL file)
+    /// write them next to `file_name`.
+    pub fn export_to_file(&self,
+                          scene: &Scene,
+                          format_id: &str,
+                          file_name: &str)
+                          -> Result<(), ExportError> {
+        let cformat_id = check_format_id(format_id)?;
+        let cfile_name = match CString::new(file_name) {
+            Ok(s) => s,
+            Err(_) => return Err(ExportError::InvalidPath(
+                format!("file name contains a nul byte: {:?}", file_name))),
+        };
+
+        let res = unsafe {
+            ffi::aiExportScene(scene.as_raw_scene(),
+                               cformat_id.as_ptr(),
+                               cfile_name.as_ptr(),
+                               self.flags)
+        };
+        match res {
+            Return::Success => Ok(()),
+            Return::OutOfMemory => Err(ExportError::OutOfMemory),
+            Return::Failure => Err(ExportError::ExportFailed(
+                format!("failed to write {:?} as {:?}", file_name, format_id))),
+        }
+    }
+
+    /// Export `scene` to memory using the format with the id `format_id`.
+    ///
+    /// Exporters which produce several files (e.g. OBJ and its MTL file)
+    /// return one blob per file. Iterate over the returned `ExportBlobs` to
+    /// get at the data.
+    pub fn export_to_blobs(&self,
+                           scene: &Scene,
+                           format_id: &str)
+                           -> Result<ExportBlobs, ExportError> {
+        let cformat_id = check_format_id(format_id)?;
+
+        let raw = unsafe {
+            ffi::aiExportSceneToBlob(scene.as_raw_scene(),
+                                     cformat_id.as_ptr(),
+                                     self.flags)
+        };
+        if raw.is_null() {
+            Err(ExportError::ExportFailed(
+                format!("failed to export scene as {:?}", format_id)))
+        } else {
+            Ok(ExportBlobs { raw: raw })
+        }
+    }
+}
+
+/// The files written by `Exporter::export_to_blobs`.
+///
+/// The data stays owned by assimp and is released when this is dropped.
+pub struct ExportBlobs {
+    raw: *const RawExportDataBlob,
+}
+
+impl ExportBlobs {
+    /// Get the primary blob, which holds the actual scene file.
+    pub fn primary(&self) -> ExportBlob<'_> {
+        unsafe { ExportBlob::from_raw(&*self.raw) }
+    }
+
+    /// Iterate over all blobs, starting with the primary one.
+    pub fn iter(&self) -> ExportBlobIter<'_> {
+        ExportBlobIter {
+            next: self.raw,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl Drop for ExportBlobs {
+    fn drop(&mut self) {
+        unsafe { ffi::aiReleaseExportBlob(self.raw) }
+    }
+}
+
+impl<'a> IntoIterator for &'a ExportBlobs {
+    type Item = ExportBlob<'a>;
+    type IntoIter = ExportBlobIter<'a>;
+
+    fn into_iter(self) -> ExportBlobIter<'a> {
+        self.iter()
+    }
+}
+
+/// A single exported file held in memory.
+#[derive(Copy, Clone, Debug)]
+pub struct ExportBlob<'a> {
+    /// The name of the blob.
+    ///
+    /// The name is empty for the primary blob. Auxiliary blobs, such as
+    /// material files, are usually named after the file extension that
+    /// should be used when writing them to disk, without the dot (e.g.
+    /// `"mtl"`).
+    pub name: &'a str,
+
+    /// The contents of the file.
+    pub data: &'a [u8],
+}
+
+impl<'a> ExportBlob<'a> {
+    unsafe fn from_raw(raw: &'a RawExportDataBlob) -> ExportBlob<'a> {
+        ExportBlob {
+            name: str::from_utf8(raw.name.as_bytes()).unwrap_or(""),
+            data: ptr_to_slice(raw.data as *mut u8, raw.size as usize),
         }
     }
 }
 
+/// Iterates over the chain of blobs in an `ExportBlobs`.
+pub struct ExportBlobIter<'a> {
+    next: *const RawExportDataBlob,
+    marker: PhantomData<&'a ExportBlobs>,
+}
+
+impl<'a> Iterator for ExportBlobIter<'a> {
+    type Item = ExportBlob<'a>;
+
+    fn next(&mut self) -> Option<ExportBlob<'a>> {
+        if self.next.is_null() {
+            return None;
+        }
+        unsafe {
+            let raw = &*self.next;
+            self.next = raw.next;
+            Some(ExportBlob::from_raw(raw))
+        }
+    }
+}
+
+/// Check that an exporter exists for `format_id` and convert it to a
+/// `CString`.
+fn check_format_id(format_id: &str) -> Result<CString, ExportError> {
+    if !info::is_export_format_supported(format_id) {
+        return Err(ExportError::UnsupportedFormat(format_id.to_string()));
+    }
+    CString::new(format_id)
+        .map_err(|_| ExportError::UnsupportedFormat(format_id.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::{Exporter, ExportError};
+    use importer::Importer;
+
+    #[test]
+    fn test_export_to_file() {
+        let importer = Importer::new();
+        let scene = importer.import_from_file("examples/assets/cube.dae")
+                            .unwrap();
+        let exporter = Exporter::new();
+
+        let path = env::temp_dir().join(
+            format!("rust-assimp-export-test-{}.stl", process::id()));
+        let path = path.to_str().unwrap();
+        exporter.export_to_file(&scene, "stl", path).unwrap();
+        assert!(fs::metadata(path).unwrap().len() > 0);
+        fs::remove_file(path).unwrap();
+
+        match exporter.export_to_file(&scene, "no-such-format", path) {
+            Err(ExportError::UnsupportedFormat(_)) => {},
+            _ => panic!("expected an unsupported format error"),
+        };
+    }
+
+    #[test]
+    fn test_export_to_blobs() {
+        let importer = Importer::new();
+        let scene = importer.import_from_file("examples/assets/cube.dae")
+                            .unwrap();
+
+        let blobs = Exporter::new().export_to_blobs(&scene, "obj").unwrap();
+        assert_eq!(blobs.primary().name, "");
+        assert!(blobs.primary().data.len() > 0);
+
+        let names: Vec<&str> = blobs.iter().map(|blob| blob.name).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names[1].ends_with("mtl"));
+    }
+}
+
 // vim: et tw=78 sw=4:
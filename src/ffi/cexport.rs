@@ -46,7 +46,7 @@ pub struct RawExportDataBlob {
     pub size: size_t,
 
     /// The data.
-    data: *const c_void,
+    pub data: *const c_void,
 
     /// Name of the blob.
     ///
@@ -61,17 +61,7 @@ pub struct RawExportDataBlob {
     pub name: AiString,
 
     /// Pointer to the next blob in the chain or NULL if there is none.
-    next: *const RawExportDataBlob
-}
-
-struct ExportDataBlob {
-    raw: *const RawExportDataBlob,
-}
-
-impl Drop for ExportDataBlob {
-    fn drop(&mut self) {
-        unsafe { aiReleaseExportBlob(self.raw) }
-    }
+    pub next: *const RawExportDataBlob
 }
 
 extern {
@@ -95,6 +85,14 @@ extern {
     /// is out of range.
     pub fn aiGetExportFormatDescription(index: size_t) -> *const ExportFormatDesc;
 
+    /// Release a description of the nth export file format. Must be returned
+    /// by aiGetExportFormatDescription
+    ///
+    /// #Parameters
+    ///
+    /// * `desc` Pointer to the description
+    pub fn aiReleaseExportFormatDescription(desc: *const ExportFormatDesc);
+
     ///  Create a modifyable copy of a scene.
     ///
     ///  This is useful to import files via Assimp, change their topology and
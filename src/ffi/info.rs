@@ -1,9 +1,40 @@
 //! Functions for querying the version of assimp being used
 
-use libc::{c_char, c_uint};
+use libc::{c_char, c_uint, size_t};
 
 use types::{AiBool, AiString};
 
+/// Meta information about a particular importer.
+#[repr(C)]
+pub struct RawImporterDesc {
+    /// Full name of the importer (i.e. Blender3D importer)
+    pub name: *const c_char,
+
+    /// Original author (left blank if unknown or whole assimp team)
+    pub author: *const c_char,
+
+    /// Current maintainer, left blank if the author maintains
+    pub maintainer: *const c_char,
+
+    /// Implementation comments, i.e. unimplemented features
+    pub comments: *const c_char,
+
+    /// Any combination of the `ImporterFlags` enumerated values.
+    pub flags: c_uint,
+
+    /// Minimum format version that can be loaded in major.minor format.
+    pub min_major: c_uint,
+    pub min_minor: c_uint,
+
+    /// Maximum format version that can be loaded in major.minor format.
+    pub max_major: c_uint,
+    pub max_minor: c_uint,
+
+    /// List of file extensions this importer can handle, separated by
+    /// spaces.
+    pub file_extensions: *const c_char,
+}
+
 #[link(name = "assimp")]
 extern {
     pub fn aiGetLegalString() -> *const c_char;
@@ -19,4 +50,8 @@ extern {
     pub fn aiGetExtensionList(out: *mut AiString);
 
     pub fn aiIsExtensionSupported(ext: *const c_char) -> AiBool;
+
+    pub fn aiGetImportFormatCount() -> size_t;
+
+    pub fn aiGetImportFormatDescription(index: size_t) -> *const RawImporterDesc;
 }
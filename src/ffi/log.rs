@@ -1,10 +1,10 @@
 //! Data types used in the assimp C api for logging
 use libc::{c_char};
 
-use types::{AiBool};
+use types::{AiBool, Return};
 
 /// Callback function used for custom log stream
-pub type LogStreamCallback = extern fn (*const c_char /* msg */, *mut c_char /* user */);
+pub type LogStreamCallback = extern "C" fn (*const c_char /* msg */, *mut c_char /* user */);
 
 /// Enumerates predefined log streaming destinations.
 ///
@@ -101,6 +101,7 @@ extern {
     /// @see aiDetachAllLogStreams
     // ASSIMP_API C_ENUM aiReturn aiDetachLogStream(
     // const C_STRUCT aiLogStream* stream);
+    pub fn aiDetachLogStream(stream: *const LogStream) -> Return;
 
     /// Detach all active log streams from the libraries' logging system.
     ///
@@ -1,5 +1,5 @@
 
-use libc::{c_uint, c_float};
+use libc::{c_char, c_int, c_uint, c_float};
 use types;
 use material;
 
@@ -46,14 +46,59 @@ use material;
 //                                      unsigned int* flags                 /*= NULL*/);
 
 extern {
+    // The enums are passed as integers, assimp may store values which are
+    // not valid variants of the Rust enums.
     pub fn aiGetMaterialTexture(aiMaterial: *const material::Material,
                              aiTextureType: material::TextureType,
                              index: c_uint,
                              path: *mut types::AiString,
-                             mapping: *mut material::TextureMapping /*= NULL*/,
+                             mapping: *mut c_int /*= NULL*/,
                              uvindex: *mut c_uint            /*= NULL*/,
                              blend: *mut c_float                    /*= NULL*/,
-                             op: *mut material::TextureOp           /*= NULL*/,
-                             mapmode: *mut material::TextureMapMode /*= NULL*/,
+                             op: *mut c_int           /*= NULL*/,
+                             mapmode: *mut c_int /*= NULL*/,
                              flags: *mut c_uint              /*= NULL*/) -> types::Return;
+
+    /// Get the number of textures for a particular texture type.
+    pub fn aiGetMaterialTextureCount(mat: *const material::Material,
+                                     tex_type: material::TextureType)
+                                     -> c_uint;
+
+    /// Retrieve an array of float values with a specific key from the
+    /// material.
+    ///
+    /// `max` specifies the size of `out`, in floats, and receives the number
+    /// of values read. Pass NULL to read a single value. Integer properties
+    /// are converted automatically.
+    pub fn aiGetMaterialFloatArray(mat: *const material::Material,
+                                   key: *const c_char,
+                                   semantic: c_uint,
+                                   index: c_uint,
+                                   out: *mut c_float,
+                                   max: *mut c_uint) -> types::Return;
+
+    /// Retrieve an array of integer values with a specific key from the
+    /// material.
+    ///
+    /// See `aiGetMaterialFloatArray`.
+    pub fn aiGetMaterialIntegerArray(mat: *const material::Material,
+                                     key: *const c_char,
+                                     semantic: c_uint,
+                                     index: c_uint,
+                                     out: *mut c_int,
+                                     max: *mut c_uint) -> types::Return;
+
+    /// Retrieve a color value from the material property table
+    pub fn aiGetMaterialColor(mat: *const material::Material,
+                              key: *const c_char,
+                              semantic: c_uint,
+                              index: c_uint,
+                              out: *mut types::Color4D) -> types::Return;
+
+    /// Retrieve a string from the material property table
+    pub fn aiGetMaterialString(mat: *const material::Material,
+                               key: *const c_char,
+                               semantic: c_uint,
+                               index: c_uint,
+                               out: *mut types::AiString) -> types::Return;
 }
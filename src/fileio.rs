@@ -1,35 +1,126 @@
-/// Defines generic C routines to access memory-mapped files
+//! Defines the virtual file system used by the importer.
+//!
+//! By default assimp reads models straight from disk. Implement `FileSystem`
+//! and hand it to `Importer::set_file_system` to resolve the model file, and
+//! every file it references (OBJ materials, MD5 animations, MD3 skins, ...),
+//! from another source such as a zip archive or an asset database.
 
-use libc::{c_char, size_t};
+use libc::{c_char, c_uint, size_t};
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::rc::Rc;
 
-use types::{Return};
+use types::Return;
 
-// AiFile callbacks
-type FileWriteProc = extern fn (*mut AiFile, *const char, size_t, size_t) -> size_t;
-type FileReadProc = extern fn (*mut AiFile, *const char, size_t, size_t) -> size_t;
-type FileTellProc = extern fn (*mut AiFile) -> size_t;
-type FileFlushProc = extern fn (*mut AiFile);
-type FileSeek = extern fn (*mut AiFile, size_t, Origin) -> Return;
+/// A readable, seekable file returned by `FileSystem::open`.
+///
+/// This is implemented for every type implementing `Read` and `Seek`, so
+/// `std::fs::File` or `std::io::Cursor` can be returned directly.
+pub trait FileStream: Read + Seek {
+    /// Get the size of the file in bytes.
+    ///
+    /// The default implementation seeks to the end of the file and back.
+    fn size(&mut self) -> io::Result<u64> {
+        let pos = self.seek(SeekFrom::Current(0))?;
+        let size = self.seek(SeekFrom::End(0))?;
+        let _ = self.seek(SeekFrom::Start(pos))?;
+        Ok(size)
+    }
+}
 
-// AiFileIO callbacks
-type FileOpenProc = extern fn (*mut AiFileIO, *const c_char, *const c_char) -> *mut AiFile;
-type FileCloseProc = extern fn (*mut AiFileIO, *mut AiFile);
+impl<T: Read + Seek> FileStream for T {}
 
-/// Seek origins (for the virtual file system API).
+/// A read-only virtual file system.
 ///
-#[repr(C)]
-#[allow(dead_code)]
-pub enum Origin {
-    /// Beginning of the file 
-    Origin_SET = 0x0,
+/// Paths are passed on exactly as assimp builds them: files referenced by a
+/// model are usually looked up relative to the directory of the model file
+/// and may use either `/` or `\` as a separator.
+pub trait FileSystem {
+    /// Open the file at `path` for reading.
+    ///
+    /// Returns `None` if the file does not exist or can't be read.
+    fn open(&mut self, path: &str) -> Option<Box<dyn FileStream>>;
 
-    /// Current position of the file pointer 
-    Origin_CUR = 0x1,
+    /// Close a file that was returned by `open`.
+    ///
+    /// The default implementation simply drops the file.
+    fn close(&mut self, file: Box<dyn FileStream>) {
+        drop(file);
+    }
+}
 
-    /// End of the file, offsets must be negative 
-    Origin_END = 0x2,
+/// A `FileSystem` serving files from memory.
+///
+/// Backslashes in paths are treated as forward slashes and a leading `./` is
+/// ignored, both when adding files and when assimp looks them up.
+pub struct MemoryFileSystem {
+    files: HashMap<String, Rc<[u8]>>,
 }
 
+impl MemoryFileSystem {
+    /// Create an empty in-memory file system.
+    pub fn new() -> MemoryFileSystem {
+        MemoryFileSystem {
+            files: HashMap::new(),
+        }
+    }
+
+    /// Add a file, replacing any file previously stored under `path`.
+    pub fn insert(&mut self, path: &str, data: Vec<u8>) {
+        let _ = self.files.insert(normalize_path(path), Rc::from(data));
+    }
+
+    /// Check whether a file is stored under `path`.
+    pub fn contains(&self, path: &str) -> bool {
+        self.files.contains_key(&normalize_path(path))
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn open(&mut self, path: &str) -> Option<Box<dyn FileStream>> {
+        match self.files.get(&normalize_path(path)) {
+            Some(data) => Some(Box::new(Cursor::new(data.clone()))),
+            None => None,
+        }
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut path = &path[..];
+    while path.starts_with("./") {
+        path = &path[2..];
+    }
+    path.to_string()
+}
+
+// AiFile callbacks
+type FileWriteProc = extern "C" fn (*mut AiFile, *const c_char, size_t, size_t) -> size_t;
+type FileReadProc = extern "C" fn (*mut AiFile, *mut c_char, size_t, size_t) -> size_t;
+type FileTellProc = extern "C" fn (*mut AiFile) -> size_t;
+type FileFlushProc = extern "C" fn (*mut AiFile);
+type FileSeek = extern "C" fn (*mut AiFile, size_t, c_uint) -> Return;
+
+// AiFileIO callbacks
+type FileOpenProc = extern "C" fn (*mut AiFileIO, *const c_char, *const c_char) -> *mut AiFile;
+type FileCloseProc = extern "C" fn (*mut AiFileIO, *mut AiFile);
+
+// Seek origins (aiOrigin). They are received as plain integers, assimp
+// doesn't guarantee that only these values are passed.
+
+/// Beginning of the file
+const ORIGIN_SET: c_uint = 0x0;
+
+/// Current position of the file pointer
+const ORIGIN_CUR: c_uint = 0x1;
+
+/// End of the file, offsets must be negative
+const ORIGIN_END: c_uint = 0x2;
+
 /// C-API: File system callbacks
 ///
 /// Provided are functions to open and close files. Supply a custom structure
@@ -37,8 +128,7 @@ pub enum Origin {
 /// Use custom file systems to enable reading from other sources, such as ZIPs
 /// or memory locations.
 #[repr(C)]
-#[allow(dead_code)]
-pub struct AiFileIO {
+pub(crate) struct AiFileIO {
     /// Function used to open a new file
     open: FileOpenProc,
 
@@ -46,7 +136,20 @@ pub struct AiFileIO {
     close: FileCloseProc,
 
     /// User-defined, opaque data
-    user_data: *const c_char,
+    user_data: *mut c_char,
+}
+
+impl AiFileIO {
+    /// Create the callbacks forwarding to `fs`.
+    ///
+    /// The returned value must not be used after `fs` is dropped or moved.
+    pub(crate) unsafe fn new(fs: &mut Box<dyn FileSystem>) -> AiFileIO {
+        AiFileIO {
+            open: file_open,
+            close: file_close,
+            user_data: fs as *mut Box<dyn FileSystem> as *mut c_char,
+        }
+    }
 }
 
 /// File callbacks
@@ -59,7 +162,7 @@ pub struct AiFileIO {
 /// delivering a custom aiFileIO. Use this to enable reading from other sources,
 /// such as ZIP archives or memory locations.
 #[repr(C)]
-pub struct AiFile {
+pub(crate) struct AiFile {
     /// Callback to read from a file
     read: FileReadProc,
 
@@ -79,6 +182,213 @@ pub struct AiFile {
     flush: FileFlushProc,
 
     /// User-defined, opaque data
-    user_data: *const c_char,
+    user_data: *mut c_char,
 }
+
+unsafe fn file_system<'a>(io: *mut AiFileIO) -> &'a mut Box<dyn FileSystem> {
+    &mut *((*io).user_data as *mut Box<dyn FileSystem>)
+}
+
+unsafe fn file_stream<'a>(file: *mut AiFile) -> &'a mut Box<dyn FileStream> {
+    &mut *((*file).user_data as *mut Box<dyn FileStream>)
+}
+
+/// Call `f`, returning `failed` if it panics.
+///
+/// The callbacks below run user code, and panics must not unwind into
+/// assimp.
+fn catch_panic<T, F: FnOnce() -> T>(failed: T, f: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(failed)
+}
+
+extern "C" fn file_open(io: *mut AiFileIO,
+                        path: *const c_char,
+                        mode: *const c_char) -> *mut AiFile {
+    catch_panic(ptr::null_mut(), || unsafe {
+        // Only reading is supported
+        let mode = CStr::from_ptr(mode).to_bytes();
+        if mode.contains(&b'w') || mode.contains(&b'a') || mode.contains(&b'+') {
+            return ptr::null_mut();
+        }
+
+        let path = CStr::from_ptr(path).to_string_lossy();
+        let stream = match file_system(io).open(&path) {
+            Some(stream) => stream,
+            None => return ptr::null_mut(),
+        };
+
+        Box::into_raw(Box::new(AiFile {
+            read: file_read,
+            write: file_write,
+            tell: file_tell,
+            size: file_size,
+            seek: file_seek,
+            flush: file_flush,
+            user_data: Box::into_raw(Box::new(stream)) as *mut c_char,
+        }))
+    })
+}
+
+extern "C" fn file_close(io: *mut AiFileIO, file: *mut AiFile) {
+    if file.is_null() {
+        return;
+    }
+    catch_panic((), || unsafe {
+        let file = Box::from_raw(file);
+        let stream = Box::from_raw(file.user_data as *mut Box<dyn FileStream>);
+        file_system(io).close(*stream);
+    })
+}
+
+extern "C" fn file_read(file: *mut AiFile,
+                        buffer: *mut c_char,
+                        size: size_t,
+                        count: size_t) -> size_t {
+    let len = match size.checked_mul(count) {
+        Some(0) | None => return 0,
+        Some(len) => len,
+    };
+    catch_panic(0, || unsafe {
+        let stream = file_stream(file);
+        let buffer = slice::from_raw_parts_mut(buffer as *mut u8, len);
+
+        let mut read = 0;
+        while read < len {
+            match stream.read(&mut buffer[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+                Err(_) => break,
+            }
+        }
+        read / size
+    })
+}
+
+extern "C" fn file_write(_file: *mut AiFile,
+                         _buffer: *const c_char,
+                         _size: size_t,
+                         _count: size_t) -> size_t {
+    // Files are only ever opened for reading
+    0
+}
+
+extern "C" fn file_tell(file: *mut AiFile) -> size_t {
+    catch_panic(0, || unsafe {
+        match file_stream(file).seek(SeekFrom::Current(0)) {
+            Ok(pos) => pos as size_t,
+            Err(_) => 0,
+        }
+    })
+}
+
+extern "C" fn file_size(file: *mut AiFile) -> size_t {
+    catch_panic(0, || unsafe {
+        match file_stream(file).size() {
+            Ok(size) => size as size_t,
+            Err(_) => 0,
+        }
+    })
+}
+
+extern "C" fn file_seek(file: *mut AiFile,
+                        offset: size_t,
+                        origin: c_uint) -> Return {
+    // Relative offsets are passed as wrapped around unsigned values
+    let pos = match origin {
+        ORIGIN_SET => SeekFrom::Start(offset as u64),
+        ORIGIN_CUR => SeekFrom::Current(offset as isize as i64),
+        ORIGIN_END => SeekFrom::End(offset as isize as i64),
+        _ => return Return::Failure,
+    };
+    catch_panic(Return::Failure, || unsafe {
+        match file_stream(file).seek(pos) {
+            Ok(_) => Return::Success,
+            Err(_) => Return::Failure,
+        }
+    })
+}
+
+extern "C" fn file_flush(_file: *mut AiFile) {}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::fs;
+    use std::io::Read;
+    use std::rc::Rc;
+
+    use super::{normalize_path, FileStream, FileSystem, MemoryFileSystem};
+    use fixtures::{find_material, OBJ};
+    use importer::Importer;
+
+    struct PanickingFileSystem;
+
+    /// Remembers the paths of all files opened through it
+    struct RecordingFileSystem {
+        files: MemoryFileSystem,
+        opened: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl FileSystem for RecordingFileSystem {
+        fn open(&mut self, path: &str) -> Option<Box<dyn FileStream>> {
+            self.opened.borrow_mut().push(normalize_path(path));
+            self.files.open(path)
+        }
+    }
+
+    impl FileSystem for PanickingFileSystem {
+        fn open(&mut self, _path: &str) -> Option<Box<dyn FileStream>> {
+            panic!("file system failure");
+        }
+    }
+
+    #[test]
+    fn test_memory_file_system() {
+        let mut vfs = MemoryFileSystem::new();
+        vfs.insert("models\\cube.dae",
+                   fs::read("examples/assets/cube.dae").unwrap());
+        assert!(vfs.contains("./models/cube.dae"));
+
+        let mut file = vfs.open("models/cube.dae").unwrap();
+        let mut contents = String::new();
+        let _ = file.read_to_string(&mut contents).unwrap();
+        assert!(contents.contains("COLLADA"));
+        assert!(vfs.open("models/missing.dae").is_none());
+
+        let mut imp = Importer::new();
+        imp.set_file_system(vfs);
+        let scene = imp.import_from_file("models/cube.dae").unwrap();
+        assert!(scene.num_meshes > 0);
+    }
+
+    #[test]
+    fn test_sibling_files() {
+        let mut files = MemoryFileSystem::new();
+        files.insert("models/test.obj", OBJ.as_bytes().to_vec());
+        files.insert("models/test.mtl", b"newmtl red\nKd 1 0 0\n".to_vec());
+        let opened = Rc::new(RefCell::new(Vec::new()));
+
+        let mut imp = Importer::new();
+        imp.set_file_system(RecordingFileSystem {
+            files: files,
+            opened: opened.clone(),
+        });
+        let scene = imp.import_from_file("models/test.obj").unwrap();
+
+        // The material library can only be read through the file system
+        assert!(opened.borrow().iter().any(|path| path == "models/test.mtl"));
+        let diffuse = find_material(&scene, "red").get_diffuse_color()
+                                                  .unwrap();
+        assert_eq!((diffuse.r, diffuse.g, diffuse.b), (1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_panicking_file_system() {
+        let mut imp = Importer::new();
+        imp.set_file_system(PanickingFileSystem);
+        assert!(imp.import_from_file("models/cube.dae").is_err());
+    }
+}
+
 // vim: et tw=78 sw=4:
@@ -0,0 +1,57 @@
+//! Scenes shared by the tests of several modules
+
+use fileio::MemoryFileSystem;
+use importer::Importer;
+use material::Material;
+use scene::Scene;
+
+/// A single triangle using the material `red` from `test.mtl`
+pub static OBJ: &'static str = "mtllib test.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+usemtl red
+f 1 2 3
+";
+
+/// Import `obj` as `test.obj`, with `mtl` as the material file `test.mtl`
+pub fn import_obj(obj: &str, mtl: &str) -> Scene {
+    let mut vfs = MemoryFileSystem::new();
+    vfs.insert("test.obj", obj.as_bytes().to_vec());
+    vfs.insert("test.mtl", mtl.as_bytes().to_vec());
+    let mut importer = Importer::new();
+    importer.set_file_system(vfs);
+    importer.import_from_file("test.obj").unwrap()
+}
+
+/// Import a glTF 2.0 triangle using the first of `materials`, a JSON array
+/// of glTF materials. Texture 0 refers to the external image `mr.png`.
+pub fn import_gltf(materials: &str) -> Scene {
+    let gltf = [r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [ { "nodes": [ 0 ] } ],
+    "nodes": [ { "mesh": 0 } ],
+    "meshes": [ { "primitives": [
+        { "attributes": { "POSITION": 0 }, "material": 0 } ] } ],
+    "materials": "#, materials, r#",
+    "textures": [ { "source": 0 } ],
+    "images": [ { "uri": "mr.png" } ],
+    "buffers": [ { "byteLength": 36, "uri":
+        "data:application/octet-stream;base64,"#,
+        "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA", r#"" } ],
+    "bufferViews": [ { "buffer": 0, "byteLength": 36 } ],
+    "accessors": [ { "bufferView": 0, "componentType": 5126, "count": 3,
+                     "type": "VEC3", "min": [ 0, 0, 0 ], "max": [ 1, 1, 0 ] } ]
+}"#].concat();
+    Importer::new().import_from_memory(gltf.as_bytes(), "gltf").unwrap()
+}
+
+/// Get the material of `scene` named `name`
+pub fn find_material<'a>(scene: &'a Scene, name: &str) -> &'a Material {
+    scene.get_materials().iter()
+        .find(|mat| mat.get_name().as_ref().map(|s| &s[..]) == Some(name))
+        .expect("material not found")
+}
+
+// vim: et tw=78 sw=4:
@@ -1,22 +1,110 @@
 //! Defines the scene importer
 
-use libc::{c_int, c_char};
+use libc::{c_int, c_uint, c_char};
 // use std::c_str::ToCStr;
 use std::ptr;
-use std::ffi::CString;
+use std::fmt;
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::{CString, CStr};
 
 use scene::Scene;
 use ffi;
 use postprocess::Process;
 use property::Property;
 use types::AiString;
+use fileio::{AiFileIO, FileSystem};
+use log::{self, LogMessage};
 
 use importer::PropertyType::{PBool, PInt, PFloat, PStr};
 
+/// The reasons an import can fail.
+///
+/// Apart from errors caused by invalid arguments (an interior nul byte or a
+/// buffer that is too large), every variant carries the error text reported
+/// by assimp for the failed import.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ImportError {
+    /// The file could not be opened, or its name contains an interior nul
+    /// byte and can't be passed to assimp at all.
+    InvalidPath(String),
+
+    /// The format hint passed to `Importer::import_from_memory` contains an
+    /// interior nul byte.
+    InvalidHint(String),
+
+    /// The buffer passed to `Importer::import_from_memory` is larger than
+    /// the `u32::MAX` bytes assimp can import from memory.
+    BufferTooLarge(String),
+
+    /// None of the importers compiled into assimp recognised the file format.
+    UnsupportedFormat(String),
+
+    /// The file format was recognised but the file could not be parsed.
+    ParseFailed(String),
+
+    /// The file was parsed but the scene was rejected by the
+    /// `Process::ValidateDataStructure` step.
+    ValidationFailed(String),
+}
+
+impl ImportError {
+    /// Get the error message describing why the import failed.
+    pub fn message(&self) -> &str {
+        match *self {
+            ImportError::InvalidPath(ref msg) => msg,
+            ImportError::InvalidHint(ref msg) => msg,
+            ImportError::BufferTooLarge(ref msg) => msg,
+            ImportError::UnsupportedFormat(ref msg) => msg,
+            ImportError::ParseFailed(ref msg) => msg,
+            ImportError::ValidationFailed(ref msg) => msg,
+        }
+    }
+
+    /// Build an error from the message of the last failed import.
+    pub(crate) fn from_last_error() -> ImportError {
+        let msg = unsafe {
+            let pstr = ffi::aiGetErrorString();
+            if pstr.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(pstr).to_string_lossy().into_owned()
+            }
+        };
+
+        if msg.starts_with("Unable to open file") {
+            ImportError::InvalidPath(msg)
+        } else if msg.starts_with("No suitable reader found") {
+            ImportError::UnsupportedFormat(msg)
+        } else if msg.starts_with("Validation failed") {
+            ImportError::ValidationFailed(msg)
+        } else {
+            ImportError::ParseFailed(msg)
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match *self {
+            ImportError::InvalidPath(_) => "invalid path",
+            ImportError::InvalidHint(_) => "invalid format hint",
+            ImportError::BufferTooLarge(_) => "buffer too large",
+            ImportError::UnsupportedFormat(_) => "unsupported format",
+            ImportError::ParseFailed(_) => "parse failed",
+            ImportError::ValidationFailed(_) => "validation failed",
+        };
+        write!(f, "import error ({}): {}", kind, self.message())
+    }
+}
+
+impl Error for ImportError {}
+
 /// A scene importer.
 pub struct Importer {
     property_store: *mut ffi::PropertyStore,
     flags: u32,
+    file_system: RefCell<Option<Box<dyn FileSystem>>>,
 }
 
 impl Importer {
@@ -25,6 +113,7 @@ impl Importer {
         Importer {
             property_store: unsafe { ffi::aiCreatePropertyStore() },
             flags: 0,
+            file_system: RefCell::new(None),
         }
     }
 
@@ -90,28 +179,114 @@ impl Importer {
         }
     }
 
+    /// Read files through the given virtual file system.
+    ///
+    /// `import_from_file` will open the model file, and every other file
+    /// the model references, through `fs` instead of reading from disk.
+    pub fn set_file_system<F: FileSystem + 'static>(&mut self, fs: F) {
+        self.file_system = RefCell::new(Some(Box::new(fs)));
+    }
+
+    /// Go back to reading files from disk.
+    pub fn reset_file_system(&mut self) {
+        self.file_system = RefCell::new(None);
+    }
+
     /// Create a `Scene` from the given file.
-    pub fn import_from_file(&self, file_name: &str) -> Option<Scene> {
+    ///
+    /// On failure the returned `ImportError` carries assimp's description of
+    /// what went wrong.
+    pub fn import_from_file(&self, file_name: &str)
+                            -> Result<Scene, ImportError> {
+        let cfile_name = match CString::new(file_name) {
+            Ok(s) => s,
+            Err(_) => return Err(ImportError::InvalidPath(
+                format!("file name contains a nul byte: {:?}", file_name))),
+        };
+        let mut file_system = self.file_system.borrow_mut();
+        // `file_system` stays borrowed until the import is done
+        let mut file_io = file_system.as_mut()
+                                     .map(|fs| unsafe { AiFileIO::new(fs) });
+        let file_io_ptr = match file_io {
+            Some(ref mut io) => io as *mut AiFileIO,
+            None => ptr::null_mut(), // use the default file io system
+        };
         unsafe {
-            let cfile_name = CString::new(file_name).unwrap();
             let raw = ffi::aiImportFileExWithProperties(
-                cfile_name.as_ptr(), 
-                self.flags, 
-                ptr::null_mut(), 
+                cfile_name.as_ptr(),
+                self.flags,
+                file_io_ptr,
                 self.property_store as *const ffi::PropertyStore);
-            //let raw = file_name.with_c_str(|file|
-                //ffi::aiImportFileExWithProperties(
-                    //file,
-                    //self.flags,
-                    //ptr::null_mut(), // no custom file io system
-                    //self.property_store as *const ffi::PropertyStore));
             if raw.is_null() {
-                None
+                Err(ImportError::from_last_error())
             } else {
-                Some(Scene::from_raw_scene(raw))
+                Ok(Scene::from_raw_scene(raw))
             }
         }
     }
+
+    /// Create a `Scene` from the given file, collecting the messages assimp
+    /// logs during the import.
+    ///
+    /// The messages are returned whether or not the import succeeds. See
+    /// `log::capture_messages` for details.
+    pub fn import_from_file_with_log(&self, file_name: &str)
+                                     -> (Result<Scene, ImportError>,
+                                         Vec<LogMessage>) {
+        log::capture_messages(|| self.import_from_file(file_name))
+    }
+
+    /// Create a `Scene` from a file that has already been loaded into memory.
+    ///
+    /// The post processing steps and import properties of this importer are
+    /// applied just like with `import_from_file`. The returned `Scene` owns
+    /// its data, so `buffer` may be dropped as soon as this call returns.
+    ///
+    /// # Parameters
+    ///
+    /// * `buffer` The contents of the file, at most `u32::MAX` bytes.
+    /// * `hint` The file extension of the data without a leading dot, for
+    ///   example `"obj"`. Assimp tries the matching importer first and falls
+    ///   back to detecting the format itself. May be empty.
+    ///
+    /// Formats that spread their data across several files (OBJ + MTL, MD3
+    /// skins, ...) can only be partially loaded this way.
+    pub fn import_from_memory(&self, buffer: &[u8], hint: &str)
+                              -> Result<Scene, ImportError> {
+        let chint = match CString::new(hint) {
+            Ok(s) => s,
+            Err(_) => return Err(ImportError::InvalidHint(
+                format!("format hint contains a nul byte: {:?}", hint))),
+        };
+        if buffer.len() > c_uint::MAX as usize {
+            return Err(ImportError::BufferTooLarge(
+                format!("buffer of {} bytes is too large to import",
+                        buffer.len())));
+        }
+        unsafe {
+            let raw = ffi::aiImportFileFromMemoryWithProperties(
+                buffer.as_ptr() as *const c_char,
+                buffer.len() as c_uint,
+                self.flags,
+                chint.as_ptr(),
+                self.property_store as *const ffi::PropertyStore);
+            if raw.is_null() {
+                Err(ImportError::from_last_error())
+            } else {
+                Ok(Scene::from_raw_scene(raw))
+            }
+        }
+    }
+
+    /// Create a `Scene` from a file in memory, collecting the messages
+    /// assimp logs during the import.
+    ///
+    /// See `import_from_memory` and `import_from_file_with_log`.
+    pub fn import_from_memory_with_log(&self, buffer: &[u8], hint: &str)
+                                       -> (Result<Scene, ImportError>,
+                                           Vec<LogMessage>) {
+        log::capture_messages(|| self.import_from_memory(buffer, hint))
+    }
 }
 
 impl Drop for Importer {
@@ -238,7 +413,10 @@ mod test {
     // use std::io::MemWriter;
     // use std::io::BufReader;
 
-    use super::Importer;
+    use std::fs;
+
+    use super::{Importer, ImportError};
+    use log::Severity;
     use property::Property;
 
     // Log to memory
@@ -258,6 +436,57 @@ mod test {
 
         let _ = imp.import_from_file("examples/assets/cube.dae");
     }
+
+    #[test]
+    fn test_import_errors() {
+        let imp = Importer::new();
+
+        match imp.import_from_file("examples/assets/cube\0.dae") {
+            Err(ImportError::InvalidPath(_)) => {},
+            _ => panic!("expected an invalid path error"),
+        }
+
+        match imp.import_from_file("examples/assets/does_not_exist.dae") {
+            Err(ImportError::InvalidPath(msg)) => assert!(!msg.is_empty()),
+            _ => panic!("expected an invalid path error"),
+        }
+
+        match imp.import_from_file("examples/assets/guard-md5/LICENSE.md") {
+            Err(ImportError::UnsupportedFormat(msg)) => assert!(!msg.is_empty()),
+            _ => panic!("expected an unsupported format error"),
+        };
+    }
+
+    #[test]
+    fn test_import_from_memory() {
+        let buffer = fs::read("examples/assets/cube.dae").unwrap();
+        let imp = Importer::new();
+
+        let scene = imp.import_from_memory(&buffer, "dae").unwrap();
+        drop(buffer);
+        assert!(scene.num_meshes > 0);
+
+        match imp.import_from_memory(b"not a model", "dae\0") {
+            Err(ImportError::InvalidHint(_)) => {},
+            _ => panic!("expected an invalid hint error"),
+        };
+    }
+
+    #[test]
+    fn test_import_with_log() {
+        let imp = Importer::new();
+
+        let (scene, messages) =
+            imp.import_from_file_with_log("examples/assets/cube.dae");
+        assert!(scene.is_ok());
+        assert!(!messages.is_empty());
+        assert!(messages.iter().all(|msg| msg.severity != Severity::Error));
+
+        let (scene, messages) =
+            imp.import_from_file_with_log("examples/assets/missing.dae");
+        assert!(scene.is_err());
+        assert!(messages.iter().any(|msg| msg.severity == Severity::Error));
+    }
 }
 
 // vim: et tw=78 sw=4:
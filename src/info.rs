@@ -7,6 +7,7 @@ use ffi;
 use std::ffi::CString;
 use std::str::from_utf8;
 use std::ffi::CStr;
+use libc::{c_char, size_t};
 
 /// Flags for checking how assimp was compiled
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -28,99 +29,87 @@ pub enum CompileFlags {
     SingleThreaded = 0x10,
 }
 
-// It appears assimp doesn't expose a way to get this information using the
-// c-api.
-
-// /// #aiImporterFlags, aiImporterDesc implementation.//{{{
-
-// /// Mixed set of flags for #aiImporterDesc, indicating some features
-// /// common to many importers*/
-// #[repr(C)]
-// pub enum ImporterFlags {
-//     /// Indicates that there is a textual encoding of the
-//     /// file format; and that it is supported.
-//     aiImporterFlags_SupportTextFlavour = 0x1,
-
-//     /// Indicates that there is a binary encoding of the
-//     /// file format; and that it is supported.
-//     aiImporterFlags_SupportBinaryFlavour = 0x2,
-
-//     /// Indicates that there is a compressed encoding of the
-//     /// file format; and that it is supported.
-//     aiImporterFlags_SupportCompressedFlavour = 0x4,
-
-//     /// Indicates that the importer reads only a very particular subset of the
-//     /// file format.
-//     ///
-//     /// This happens commonly for declarative or procedural formats which
-//     /// cannot easily be mapped to #aiScene
-//     aiImporterFlags_LimitedSupport = 0x8,
-
-//     /// Indicates that the importer is highly experimental and should be used
-//     /// with care.
-//     ///
-//     /// This only happens for trunk
-//     /// (i.e. SVN) versions, experimental code is not included
-//     /// in releases. */
-//     aiImporterFlags_Experimental = 0x10,
-// }
-// /// Meta information about a particular importer.
-// ///
-// /// Importers need to fill this structure, but they can freely decide how
-// /// talkative they are.  A common use case for loader meta info is a user
-// /// interface in which the user can choose between various import/export file
-// /// formats. Building such an UI by hand means a lot of maintenance as
-// /// importers/exporters are added to Assimp, so it might be useful to have a
-// /// common mechanism to query some rough importer characteristics.
-// #[repr(C)]
-// pub struct ImporterDesc {
-//     /// Full name of the importer (i.e. Blender3D importer)
-//     const char* mName;
-
-//     /// Original author (left blank if unknown or whole assimp team)
-//     const char* mAuthor;
-
-//     /// Current maintainer, left blank if the author maintains
-//     const char* mMaintainer;
-
-//     /// Implementation comments, i.e. unimplemented features
-//     const char* mComments;
-
-//     /// Any combination of the #aiLoaderFlags enumerated values.
-//     /// These flags indicate some characteristics common to many
-//     /// importers.
-//     unsigned int mFlags;
-
-//     /// Minimum format version that can be loaded im major.minor format,
-//     /// both are set to 0 if there is either no version scheme 
-//     /// or if the loader doesn't care.
-//     unsigned int mMinMajor;
-//     unsigned int mMinMinor;
-
-//     /// Maximum format version that can be loaded im major.minor format,
-//     /// both are set to 0 if there is either no version scheme 
-//     /// or if the loader doesn't care. Loaders that expect to be
-//     /// forward-compatible to potential future format versions should 
-//     /// indicate  zero, otherwise they should specify the current
-//     /// maximum version.
-//     unsigned int mMaxMajor;
-//     unsigned int mMaxMinor;
-
-//     /// List of file extensions this importer can handle.
-//     ///
-//     /// List entries are separated by space characters.
-//     /// All entries are lower case without a leading dot (i.e.
-//     /// "xml dae" would be a valid value. Note that multiple
-//     /// importers may respond to the same file extension -
-//     /// assimp calls all importers in the order in which they
-//     /// are registered and each importer gets the opportunity
-//     /// to load the file until one importer "claims" the file. Apart
-//     /// from file extension checks, importers typically use
-//     /// other methods to quickly reject files (i.e. magic
-//     /// words) so this does not mean that common or generic
-//     /// file extensions such as XML would be tediously slow.
-//     const char* mFileExtensions;
-// }//}}}
+/// Mixed set of flags for `ImporterDesc`, indicating some features
+/// common to many importers.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum ImporterFlags {
+    /// Indicates that there is a textual encoding of the
+    /// file format; and that it is supported.
+    SupportTextFlavour = 0x1,
+
+    /// Indicates that there is a binary encoding of the
+    /// file format; and that it is supported.
+    SupportBinaryFlavour = 0x2,
+
+    /// Indicates that there is a compressed encoding of the
+    /// file format; and that it is supported.
+    SupportCompressedFlavour = 0x4,
+
+    /// Indicates that the importer reads only a very particular subset of the
+    /// file format.
+    ///
+    /// This happens commonly for declarative or procedural formats which
+    /// cannot easily be mapped to `Scene`
+    LimitedSupport = 0x8,
+
+    /// Indicates that the importer is highly experimental and should be used
+    /// with care.
+    Experimental = 0x10,
+}
+
+/// Meta information about a particular importer.
+///
+/// A common use case for loader meta info is a user interface in which the
+/// user can choose between various import file formats.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ImporterDesc {
+    /// Full name of the importer (i.e. Blender3D importer)
+    pub name: String,
+
+    /// Original author (left blank if unknown or whole assimp team)
+    pub author: String,
+
+    /// Current maintainer, left blank if the author maintains
+    pub maintainer: String,
+
+    /// Implementation comments, i.e. unimplemented features
+    pub comments: String,
+
+    /// Any combination of the `ImporterFlags` values.
+    pub flags: u32,
+
+    /// Minimum format version that can be loaded as `(major, minor)`.
+    ///
+    /// Both are 0 if there is either no version scheme or if the loader
+    /// doesn't care.
+    pub min_version: (u32, u32),
+
+    /// Maximum format version that can be loaded as `(major, minor)`.
+    ///
+    /// Both are 0 if there is either no version scheme or if the loader
+    /// is expected to be forward-compatible.
+    pub max_version: (u32, u32),
+
+    /// File extensions this importer can handle, in lower case without a
+    /// leading dot.
+    ///
+    /// Multiple importers may respond to the same file extension, assimp
+    /// tries them in the order in which they are registered.
+    pub extensions: Vec<String>,
+}
+
+impl ImporterDesc {
+    /// Check if the given importer flag is set
+    pub fn check_flag(&self, flag: ImporterFlags) -> bool {
+        (self.flags & flag as u32) != 0
+    }
+
+    /// Check if the importer is flagged as experimental
+    pub fn is_experimental(&self) -> bool {
+        self.check_flag(ImporterFlags::Experimental)
+    }
+}
 
 /// Get the version number of assimp as a tuple `(major, minor, revision)`
 pub fn get_version() -> (usize, usize, usize) {
@@ -160,6 +149,114 @@ pub fn get_supported_import_exts() -> String {
     exts.as_str().unwrap().to_string()
 }
 
+/// Get a description of every importer compiled into assimp.
+pub fn get_importers() -> Vec<ImporterDesc> {
+    let count = unsafe { ffi::aiGetImportFormatCount() };
+    let mut importers = Vec::with_capacity(count as usize);
+
+    for i in 0..count {
+        unsafe {
+            let desc = ffi::aiGetImportFormatDescription(i);
+            if desc.is_null() {
+                continue;
+            }
+            let desc = &*desc;
+            importers.push(ImporterDesc {
+                name: c_str_to_string(desc.name),
+                author: c_str_to_string(desc.author),
+                maintainer: c_str_to_string(desc.maintainer),
+                comments: c_str_to_string(desc.comments),
+                flags: desc.flags,
+                min_version: (desc.min_major, desc.min_minor),
+                max_version: (desc.max_major, desc.max_minor),
+                extensions: c_str_to_string(desc.file_extensions)
+                                .split_whitespace()
+                                .map(|ext| ext.to_lowercase())
+                                .collect(),
+            });
+        }
+    }
+
+    importers
+}
+
+/// Find the importers which handle files with the given extension.
+///
+/// The extension is compared case insensitively and may include a leading
+/// dot. Importers are returned in the order in which assimp tries them.
+pub fn find_importers(ext: &str) -> Vec<ImporterDesc> {
+    let ext = ext.trim_start_matches('.').to_lowercase();
+    get_importers().into_iter()
+        .filter(|importer| importer.extensions.contains(&ext))
+        .collect()
+}
+
+/// Describes a file format assimp can export to.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExportFormat {
+    /// Short string uniquely identifying the format, e.g. `"collada"` or
+    /// `"obj"`. Pass it to the `Exporter` to select this format.
+    pub id: String,
+
+    /// A short description of the file format to present to users.
+    pub description: String,
+
+    /// Recommended file extension for the exported file in lower case,
+    /// without a leading dot.
+    pub file_ext: String,
+}
+
+/// Get a list of all file formats assimp is able to export to.
+pub fn get_export_formats() -> Vec<ExportFormat> {
+    export_formats().collect()
+}
+
+/// Find the export format with the given file extension.
+///
+/// The extension is compared case insensitively and may include a leading
+/// dot. If several formats share the extension the first one is returned.
+pub fn find_export_format(ext: &str) -> Option<ExportFormat> {
+    let ext = ext.trim_start_matches('.');
+    export_formats().find(|format| format.file_ext.eq_ignore_ascii_case(ext))
+}
+
+/// Check whether assimp can export to the format with the given id, e.g.
+/// `"collada"`.
+pub fn is_export_format_supported(id: &str) -> bool {
+    export_formats().any(|format| format.id == id)
+}
+
+/// Iterate over the export formats, reading each description only when it
+/// is needed.
+fn export_formats() -> impl Iterator<Item=ExportFormat> {
+    let count = unsafe { ffi::aiGetExportFormatCount() };
+    (0..count).filter_map(|i| unsafe { get_export_format(i) })
+}
+
+unsafe fn get_export_format(index: size_t) -> Option<ExportFormat> {
+    let desc = ffi::aiGetExportFormatDescription(index);
+    if desc.is_null() {
+        return None;
+    }
+    let format = ExportFormat {
+        id: c_str_to_string((*desc).id),
+        description: c_str_to_string((*desc).description),
+        file_ext: c_str_to_string((*desc).file_ext).to_lowercase(),
+    };
+    // Since assimp 4.0 every description is a copy owned by the caller,
+    // older versions lack this function
+    ffi::aiReleaseExportFormatDescription(desc);
+    Some(format)
+}
+
+unsafe fn c_str_to_string(s: *const c_char) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(s).to_string_lossy().into_owned()
+    }
+}
+
 /// Returns the set compile flags
 pub fn get_compile_flags() -> u32 {
     unsafe { ffi::aiGetCompileFlags() }
@@ -187,6 +284,7 @@ pub fn is_ext_supported(ext: &str) -> bool {
 #[cfg(test)]
 mod test {
     use info;
+    use info::ImporterFlags;
 
     #[test]
     fn test_version() {
@@ -205,6 +303,36 @@ mod test {
         println!("support md3 : {}", info::is_ext_supported(".md3"));
         println!("support mad : {}", info::is_ext_supported(".mad"));
     }
+
+    #[test]
+    fn test_importers() {
+        let importers = info::get_importers();
+        assert!(!importers.is_empty());
+
+        let collada = info::find_importers(".DAE");
+        assert!(!collada.is_empty());
+        assert!(collada[0].extensions.iter().any(|ext| ext == "dae"));
+        assert!(info::find_importers("no-such-ext").is_empty());
+
+        // The Collada importer is not experimental
+        let mut desc = collada[0].clone();
+        assert!(!desc.is_experimental());
+        desc.flags |= ImporterFlags::Experimental as u32;
+        assert!(desc.is_experimental());
+        assert!(desc.check_flag(ImporterFlags::Experimental));
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let formats = info::get_export_formats();
+        assert!(formats.iter().any(|format| format.id == "obj"));
+
+        let stl = info::find_export_format(".STL").unwrap();
+        assert_eq!(stl.file_ext, "stl");
+        assert!(info::find_export_format("no-such-ext").is_none());
+        assert!(info::is_export_format_supported("stl"));
+        assert!(!info::is_export_format_supported("no-such-format"));
+    }
 }
 
 // vim: et tw=78 sw=4:
@@ -1,4 +1,6 @@
 //! A binding for assimp: the Open Asset Import Library
+//!
+//! The data structures match the layout used by assimp 4.x.
 
 #![crate_name = "assimp"]
 #![crate_type = "rlib"]
@@ -14,16 +16,21 @@
 
 extern crate libc;
 extern crate vecmath;
+#[cfg(feature = "log")]
+extern crate log as rlog;
+#[cfg(feature = "image")]
+extern crate image;
 
 pub use types::{Vector2D, Vector3D, Color3D, Color4D, Matrix3x3, Matrix4x4,
-                Quaternion, Plane, Ray, AiString};
+                Quaternion, Plane, Ray, AiString, Aabb, BoundingSphere};
 pub use scene::Scene;
 
 pub use property::Property;
 pub use property::Component;
 pub use property::TransformUV;
 pub use postprocess::Process;
-pub use importer::Importer;
+pub use importer::{Importer, ImportError};
+pub use exporter::{Exporter, ExportError};
 
 
 pub mod animation;
@@ -31,17 +38,23 @@ pub mod camera;
 pub mod info;
 pub mod light;
 pub mod material;
+pub mod pbr;
 pub mod mesh;
 pub mod scene;
+pub mod skinning;
 pub mod texture;
 pub mod types;
+pub mod vertex;
 pub mod importer;
+pub mod exporter;
 pub mod log;
+pub mod fileio;
 
 mod property;
 mod postprocess;
 mod util;
 mod ffi;
-mod fileio;
+#[cfg(test)]
+mod fixtures;
 
 // vim: et tw=78 sw=4:
@@ -1,12 +1,22 @@
 //! Defines logging functions
 
+use libc::c_char;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::io::Write;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use types::AiBool;
 use ffi;
 
-pub use log::LogStream::{Stdout, Stderr, Debugger, File /* TODO: ,Custom */ };
-use std::ffi::CString;
+pub use log::LogStream::{Stdout, Stderr, Debugger, File, Custom, Writer};
+#[cfg(feature = "log")]
+pub use log::LogStream::Log;
 
 /// Default logging options for assimp
 pub enum LogStream<'a> {
@@ -19,38 +29,233 @@ pub enum LogStream<'a> {
     Debugger,
     /// Log to the given file
     File(&'a str),
-    // /// TODO
-    // /// Log to the given writer
-    // Custom(&'a mut (Writer+'a))
-}
-
-// TODO//{{{
-// pub struct Logger {
-//     log: Vec<LogStream>
-// }
-
-// pub fn get_error_string() -> Option<String> {
-//     unsafe {
-//         let pstr = ffi::aiGetErrorString();
-//         if pstr.is_null() {
-//             return None;
-//         }
-//         let cstr = CString::new(pstr as *const i8, false);
-//         match cstr {
-//             Some(ss) => Some(ss.into_string()),
-//             None => None,
-//         }
-//     }
-// }
-
-// extern fn stream_call_back(msg: *const c_char, data: *const u8) {
-//     unsafe {
-//         // this code should work, but gives an Internal Compiler Error
-//         // let writer = data as *mut Writer;
-//         let cstr = CString::new(msg, false);
-//         (*stream).write(cstr.as_bytes()).unwrap();
-//     }
-// }//}}}
+    /// Pass every message to the given function, along with its severity.
+    ///
+    /// The message is stripped of the severity prefix and the trailing
+    /// newline added by assimp.
+    Custom(Box<dyn FnMut(Severity, &str) + Send>),
+    /// Write every message, exactly as formatted by assimp, to the given
+    /// writer
+    Writer(Box<dyn Write + Send>),
+    /// Forward every message to the `log` crate, using the target
+    /// `assimp::importer`.
+    ///
+    /// Messages are filtered by the level of the `log` crate as usual. Debug
+    /// messages are only produced by assimp with verbose logging enabled,
+    /// see `enable_verbose_logging`.
+    #[cfg(feature = "log")]
+    Log,
+}
+
+/// A single message logged by assimp.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LogMessage {
+    /// The severity of the message
+    pub severity: Severity,
+
+    /// The message text, without the severity prefix and trailing newline
+    pub text: String,
+}
+
+impl fmt::Display for LogMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.text)
+    }
+}
+
+/// The severity of a log message.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Severity {
+    /// Debugging information, only logged with verbose logging enabled
+    Debug,
+    /// Information about the import
+    Info,
+    /// Something looks wrong, but the import can continue
+    Warn,
+    /// Something went wrong, usually causing the import to fail
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Severity::Debug => "debug",
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<Severity> for ::rlog::Level {
+    fn from(severity: Severity) -> ::rlog::Level {
+        match severity {
+            Severity::Debug => ::rlog::Level::Debug,
+            Severity::Info => ::rlog::Level::Info,
+            Severity::Warn => ::rlog::Level::Warn,
+            Severity::Error => ::rlog::Level::Error,
+        }
+    }
+}
+
+/// The target used for messages forwarded to the `log` crate
+#[cfg(feature = "log")]
+const LOG_TARGET: &'static str = "assimp::importer";
+
+/// Split a message formatted by assimp's default logger, such as
+/// `"Warn,  T0: Some warning\n"`, into its severity and the message text.
+///
+/// Messages without a recognized prefix are reported as `Severity::Info`.
+fn parse_message(msg: &str) -> (Severity, &str) {
+    let msg = msg.trim_end_matches(|c| c == '\n' || c == '\r');
+    if let Some(pos) = msg.find(": ") {
+        let level = msg[..pos].split(',').next().unwrap_or("").trim();
+        let severity = match level {
+            "Debug" | "Verbose" => Some(Severity::Debug),
+            "Info" => Some(Severity::Info),
+            "Warn" => Some(Severity::Warn),
+            "Error" => Some(Severity::Error),
+            _ => None,
+        };
+        if let Some(severity) = severity {
+            return (severity, &msg[pos + 2..]);
+        }
+    }
+    (Severity::Info, msg)
+}
+
+/// The Rust side of a custom log stream.
+enum Sink {
+    Callback(Box<dyn FnMut(Severity, &str) + Send>),
+    Writer(Box<dyn Write + Send>),
+    #[cfg(feature = "log")]
+    Log,
+}
+
+impl Sink {
+    fn log(&mut self, msg: &CStr) {
+        match *self {
+            Sink::Callback(ref mut callback) => {
+                let msg = msg.to_string_lossy();
+                let (severity, text) = parse_message(&msg);
+                callback(severity, text);
+            }
+            Sink::Writer(ref mut writer) => {
+                let _ = writer.write_all(msg.to_bytes());
+            }
+            #[cfg(feature = "log")]
+            Sink::Log => {
+                let msg = msg.to_string_lossy();
+                let (severity, text) = parse_message(&msg);
+                ::rlog::log!(target: LOG_TARGET, severity.into(), "{}", text);
+            }
+        }
+    }
+}
+
+// Custom streams attached to assimp. They are kept alive here until
+// `detach_all_log_streams` detaches them from assimp.
+static SINKS: Mutex<Vec<Arc<Mutex<Sink>>>> = Mutex::new(Vec::new());
+
+/// Get the attached sink whose address was passed to assimp as `user`.
+///
+/// Callbacks only use the sink through the returned reference, so a sink
+/// removed by `detach_all_log_streams` while another thread logs to it is
+/// dropped once that thread is done with it.
+fn find_sink(user: *const c_char) -> Option<Arc<Mutex<Sink>>> {
+    let sinks = match SINKS.lock() {
+        Ok(sinks) => sinks,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    sinks.iter()
+         .find(|sink| Arc::as_ptr(sink) as *const c_char == user)
+         .cloned()
+}
+
+extern "C" fn stream_call_back(msg: *const c_char, user: *mut c_char) {
+    if msg.is_null() || user.is_null() {
+        return;
+    }
+    // Panics must not unwind into assimp
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+        let sink = match find_sink(user) {
+            Some(sink) => sink,
+            None => return,
+        };
+        let mut sink = match sink.lock() {
+            Ok(sink) => sink,
+            Err(_) => return,
+        };
+        sink.log(unsafe { CStr::from_ptr(msg) });
+    }));
+}
+
+thread_local!(
+    // The messages captured by `capture_messages` on this thread
+    static CAPTURED: RefCell<Option<Vec<LogMessage>>> = RefCell::new(None)
+);
+
+// Whether the stream feeding `CAPTURED` is attached to assimp
+static CAPTURE_ATTACHED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn capture_call_back(msg: *const c_char, _user: *mut c_char) {
+    if msg.is_null() {
+        return;
+    }
+    let msg = unsafe { CStr::from_ptr(msg) }.to_string_lossy();
+    let (severity, text) = parse_message(&msg);
+    let _ = CAPTURED.try_with(|captured| {
+        if let Ok(mut captured) = captured.try_borrow_mut() {
+            if let Some(ref mut messages) = *captured {
+                messages.push(LogMessage {
+                    severity: severity,
+                    text: text.to_string(),
+                });
+            }
+        }
+    });
+}
+
+/// Restores the previous capture buffer, even if the captured call panics.
+struct CaptureGuard {
+    previous: Option<Vec<LogMessage>>,
+}
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CAPTURED.with(|captured| *captured.borrow_mut() = previous);
+    }
+}
+
+/// Call `f` and collect every message assimp logs on the current thread
+/// while it runs.
+///
+/// Assimp logs on the thread that imports a file, so messages from imports
+/// running on other threads at the same time are not mixed in. Messages are
+/// still passed to all attached log streams as well. Debug messages are only
+/// captured with verbose logging enabled.
+pub fn capture_messages<T, F: FnOnce() -> T>(f: F) -> (T, Vec<LogMessage>) {
+    if !CAPTURE_ATTACHED.swap(true, Ordering::SeqCst) {
+        let log = ffi::LogStream {
+            callback: capture_call_back,
+            user: ptr::null_mut(),
+        };
+        unsafe { ffi::aiAttachLogStream(&log) }
+    }
+
+    let previous = CAPTURED.with(|captured| {
+        captured.borrow_mut().replace(Vec::new())
+    });
+    let guard = CaptureGuard { previous: previous };
+    let result = f();
+    let messages = CAPTURED.with(|captured| captured.borrow_mut().take());
+    drop(guard);
+
+    (result, messages.unwrap_or_default())
+}
 
 /// Enable/Disable verbose logging for all log streams
 pub fn enable_verbose_logging(choice: bool) {
@@ -61,6 +266,8 @@ pub fn enable_verbose_logging(choice: bool) {
 
 /// Attach a log stream to assimp. Multiple log streams may be attach
 /// simultaneously
+///
+/// Custom streams may be called from any thread that is importing a file.
 pub fn add_log_stream(log_type: LogStream) {
     unsafe {
         let null = ptr::null();
@@ -74,26 +281,161 @@ pub fn add_log_stream(log_type: LogStream) {
                 ffi::aiGetPredefinedLogStream(ffi::DefaultLogStream_STDERR, null),
             Debugger =>
                 ffi::aiGetPredefinedLogStream(ffi::DefaultLogStream_DEBUGGER, null),
-            // // TODO
-            // Custom(_writer) => {
-            //     // writer.write_be_u32(0u32);
-            //     // ffi::LogStream {
-            //     //     callback: stream_call_back,
-            //     //     // user data will be used to reference our writer
-            //     //     user: mem::transmute(writer),
-            //     // }
-            //     unimplemented!();
-            // }
+            Custom(callback) => attach_sink(Sink::Callback(callback)),
+            Writer(writer) => attach_sink(Sink::Writer(writer)),
+            #[cfg(feature = "log")]
+            Log => attach_sink(Sink::Log),
         };
         ffi::aiAttachLogStream(&log);
     }
 }
 
+/// Keep `sink` alive and build the log stream forwarding to it.
+fn attach_sink(sink: Sink) -> ffi::LogStream {
+    let sink = Arc::new(Mutex::new(sink));
+    let user = Arc::as_ptr(&sink) as *mut c_char;
+    match SINKS.lock() {
+        Ok(mut sinks) => sinks.push(sink),
+        Err(poisoned) => poisoned.into_inner().push(sink),
+    }
+    ffi::LogStream {
+        callback: stream_call_back,
+        user: user,
+    }
+}
+
 /// Closes all log streams
+///
+/// This also drops all custom streams and writers, waiting for the ones
+/// which are still handling a message on another thread. `capture_messages`
+/// keeps working, it attaches its own stream again when needed.
 pub fn detach_all_log_streams() {
     unsafe {
         ffi::aiDetachAllLogStreams();
     }
+    CAPTURE_ATTACHED.store(false, Ordering::SeqCst);
+    // Dropped once the lock is released, sinks may run arbitrary code then
+    let _detached = match SINKS.lock() {
+        Ok(mut sinks) => mem::replace(&mut *sinks, Vec::new()),
+        Err(poisoned) => mem::replace(&mut *poisoned.into_inner(), Vec::new()),
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    use super::{add_log_stream, parse_message, Custom, Severity, Writer};
+    #[cfg(feature = "log")]
+    use super::{Sink, LOG_TARGET};
+    #[cfg(feature = "log")]
+    use std::ffi::CString;
+    use importer::Importer;
+
+    /// A writer appending to a buffer shared with the test
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "log")]
+    static RECORDS: Mutex<Vec<(String, ::rlog::Level, String)>> =
+        Mutex::new(Vec::new());
+
+    #[cfg(feature = "log")]
+    struct TestLogger;
+
+    #[cfg(feature = "log")]
+    impl ::rlog::Log for TestLogger {
+        fn enabled(&self, _metadata: &::rlog::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &::rlog::Record) {
+            RECORDS.lock().unwrap().push((record.target().to_string(),
+                                          record.level(),
+                                          record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn test_parse_message() {
+        assert_eq!(parse_message("Warn,  T0: Some warning\n"),
+                   (Severity::Warn, "Some warning"));
+        assert_eq!(parse_message("Error, T3: Bad: things"),
+                   (Severity::Error, "Bad: things"));
+        assert_eq!(parse_message("Debug, T0: x"), (Severity::Debug, "x"));
+        assert_eq!(parse_message("Info,  T0: Load foo.obj"),
+                   (Severity::Info, "Load foo.obj"));
+        assert_eq!(parse_message("no prefix"), (Severity::Info, "no prefix"));
+    }
+
+    #[test]
+    fn test_custom_streams() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let sink = messages.clone();
+        add_log_stream(Custom(Box::new(move |severity, text: &str| {
+            sink.lock().unwrap().push((severity, text.to_string()));
+        })));
+        let written = Arc::new(Mutex::new(Vec::new()));
+        add_log_stream(Writer(Box::new(SharedBuffer(written.clone()))));
+
+        // Other tests log at the same time, so only look for these imports
+        let imp = Importer::new();
+        assert!(imp.import_from_file("examples/assets/cube.dae").is_ok());
+        assert!(imp.import_from_file("examples/assets/missing.dae").is_err());
+
+        let messages = messages.lock().unwrap();
+        assert!(messages.iter().any(|&(severity, ref text)| {
+            severity == Severity::Info && text.contains("cube.dae")
+        }));
+        assert!(messages.iter().any(|&(severity, ref text)| {
+            severity == Severity::Error && text.contains("missing.dae")
+        }));
+        assert!(messages.iter().all(|&(_, ref text)| !text.ends_with('\n')));
+
+        let written = String::from_utf8_lossy(&written.lock().unwrap())
+                          .into_owned();
+        assert!(written.lines().any(|line| {
+            line.starts_with("Error,") && line.contains("missing.dae")
+        }));
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_log_sink() {
+        ::rlog::set_logger(&TestLogger).unwrap();
+        ::rlog::set_max_level(::rlog::LevelFilter::Trace);
+
+        let mut sink = Sink::Log;
+        for msg in ["Debug, T0: a\n", "Info,  T0: b\n", "Warn,  T0: c\n",
+                    "Error, T0: d\n"].iter() {
+            sink.log(&CString::new(*msg).unwrap());
+        }
+
+        let records = RECORDS.lock().unwrap();
+        let levels: Vec<_> = records.iter()
+            .map(|&(ref target, level, ref text)| {
+                assert_eq!(target, LOG_TARGET);
+                (level, &text[..])
+            })
+            .collect();
+        assert_eq!(levels, vec![(::rlog::Level::Debug, "a"),
+                                (::rlog::Level::Info, "b"),
+                                (::rlog::Level::Warn, "c"),
+                                (::rlog::Level::Error, "d")]);
+    }
 }
 
 // vim: et tw=78 sw=4:
@@ -1,9 +1,11 @@
 //! Defines the material system of the library
 
-use libc::{c_uchar, c_uint, c_float};
-use std::{ptr, mem};
+use libc::{c_int, c_uchar, c_uint, c_float};
+use std::fmt;
+use std::ptr;
+use std::ffi::CString;
 
-use types::{Vector2D, AiString, Return};
+use types::{Vector2D, Vector3D, Color4D, AiString, Return};
 use util::{ptr_ptr_to_slice, ptr_to_slice};
 use ffi;
 
@@ -51,6 +53,22 @@ pub enum TextureOp {
     SignedAdd = 0x5,
 }
 
+impl TextureOp {
+    fn from_int(value: i32) -> Option<TextureOp> {
+        use self::TextureOp::*;
+        let op = match value {
+            0x0 => Multiply,
+            0x1 => Add,
+            0x2 => Subtract,
+            0x3 => Divide,
+            0x4 => SmoothAdd,
+            0x5 => SignedAdd,
+            _ => return None,
+        };
+        Some(op)
+    }
+}
+
 /// Defines how UV coordinates outside the [0...1] range are handled.
 ///
 /// Commonly refered to as 'wrapping mode'.
@@ -75,6 +93,20 @@ pub enum TextureMapMode {
     Mirror = 0x2,
 }
 
+impl TextureMapMode {
+    fn from_int(value: i32) -> Option<TextureMapMode> {
+        use self::TextureMapMode::*;
+        let mode = match value {
+            0x0 => Wrap,
+            0x1 => Clamp,
+            0x2 => Mirror,
+            0x3 => Decal,
+            _ => return None,
+        };
+        Some(mode)
+    }
+}
+
 /// Defines how the mapping coords for a texture are generated.
 ///
 /// Real-time applications typically require full UV coordinates, so the use of
@@ -108,6 +140,22 @@ pub enum TextureMapping {
     Other = 0x5,
 }
 
+impl TextureMapping {
+    fn from_int(value: i32) -> Option<TextureMapping> {
+        use self::TextureMapping::*;
+        let mapping = match value {
+            0x0 => Uv,
+            0x1 => Sphere,
+            0x2 => Cylinder,
+            0x3 => Cubic,
+            0x4 => Plane,
+            0x5 => Other,
+            _ => return None,
+        };
+        Some(mapping)
+    }
+}
+
 /// Defines the purpose of a texture
 ///
 /// This is a very difficult topic. Different 3D packages support different
@@ -255,6 +303,26 @@ pub enum ShadingMode {
     Fresnel = 0xa,
 }
 
+impl ShadingMode {
+    fn from_int(value: i32) -> Option<ShadingMode> {
+        use self::ShadingMode::*;
+        let mode = match value {
+            0x1 => Flat,
+            0x2 => Gouraud,
+            0x3 => Phong,
+            0x4 => Blinn,
+            0x5 => Toon,
+            0x6 => OrenNayar,
+            0x7 => Minnaert,
+            0x8 => CookTorrance,
+            0x9 => NoShading,
+            0xa => Fresnel,
+            _ => return None,
+        };
+        Some(mode)
+    }
+}
+
 /// Defines some mixed flags for a particular texture.
 ///
 /// Usually you'll instruct your cg artists how textures have to look like ...
@@ -340,6 +408,59 @@ pub struct UVTransform {
     pub rotation: c_float,
 }
 
+/// Everything a material specifies about one of its textures.
+///
+/// Returned by `Material::get_texture`. Values the material doesn't specify
+/// are set to assimp's defaults.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TextureSlot {
+    /// The path of the texture.
+    ///
+    /// Embedded textures are referenced as `"*<index>"`, with `<index>`
+    /// being an index into `Scene::get_textures`.
+    pub path: String,
+
+    /// How the mapping coordinates for the texture are generated.
+    ///
+    /// Defaults to `TextureMapping::Uv`, or to `TextureMapping::Other` if
+    /// the material specifies an unknown mapping.
+    pub mapping: TextureMapping,
+
+    /// The UV channel of UV mapped textures. Defaults to 0.
+    pub uv_index: u32,
+
+    /// The strength of the texture in the blend equation. Defaults to 1.
+    pub blend: f32,
+
+    /// How the texture is combined with the result of the previous layers.
+    ///
+    /// `None` if the material doesn't specify an operation, in which case
+    /// the texture is usually multiplied with the base color for the first
+    /// layer and added for any further layers.
+    pub op: Option<TextureOp>,
+
+    /// How coordinates outside [0..1] are handled on the u and v axes.
+    /// Defaults to `TextureMapMode::Wrap`.
+    pub map_mode: [TextureMapMode; 2],
+
+    /// Any combination of the `TextureFlags`. Defaults to 0.
+    pub flags: u32,
+
+    /// The transformation of the UV coordinates, if any.
+    pub uv_transform: Option<UVTransform>,
+
+    /// The main axis of non-UV mappings (e.g. the axis of a cylinder
+    /// mapping), if any.
+    pub map_axis: Option<Vector3D>,
+}
+
+impl TextureSlot {
+    /// Check if the given texture flag is set
+    pub fn check_flag(&self, flag: TextureFlags) -> bool {
+        (self.flags & flag as u32) != 0
+    }
+}
+
 /// A very primitive RTTI system for the contents of material properties.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[repr(C)]
@@ -351,6 +472,11 @@ pub enum PropertyTypeInfo {
     /// The material system performs the type conversion automatically.
     PtiFloat   = 0x1,
 
+    /// Array of double-precision (64 Bit) floats
+    ///
+    /// Only used by assimp builds with double precision support.
+    PtiDouble  = 0x2,
+
     /// The material property is an AiString.
     ///
     /// Arrays of strings aren't possible, aiGetMaterialString() (or the
@@ -425,7 +551,6 @@ pub struct MaterialProperty {
     data: *mut c_uchar,
 }
 
-//TODO handle this in a rusty way
 impl MaterialProperty {
     /// Get a binary buffer that holds the property's value.
     /// The size of the buffer is always data_length.
@@ -433,6 +558,71 @@ impl MaterialProperty {
         unsafe { ptr_to_slice(self.data, self.data_length as usize) }
     }
 
+    /// Decode the property's value according to its `type_info`.
+    pub fn get_value(&self) -> MaterialValue {
+        let data = self.get_data();
+        match self.type_info {
+            PropertyTypeInfo::PtiFloat => MaterialValue::Float(
+                data.chunks_exact(4)
+                    .map(|x| f32::from_ne_bytes([x[0], x[1], x[2], x[3]]))
+                    .collect()),
+            PropertyTypeInfo::PtiDouble => MaterialValue::Double(
+                data.chunks_exact(8)
+                    .map(|x| f64::from_ne_bytes([x[0], x[1], x[2], x[3],
+                                                 x[4], x[5], x[6], x[7]]))
+                    .collect()),
+            PropertyTypeInfo::PtiInteger => MaterialValue::Integer(
+                data.chunks_exact(4)
+                    .map(|x| i32::from_ne_bytes([x[0], x[1], x[2], x[3]]))
+                    .collect()),
+            PropertyTypeInfo::PtiString => {
+                // Stored as a 32 bit length, followed by the characters and
+                // a terminating zero
+                let text = if data.len() >= 4 {
+                    let len = u32::from_ne_bytes([data[0], data[1],
+                                                  data[2], data[3]]) as usize;
+                    let end = (4 + len).min(data.len());
+                    String::from_utf8_lossy(&data[4..end]).into_owned()
+                } else {
+                    String::new()
+                };
+                MaterialValue::String(text)
+            }
+            PropertyTypeInfo::PtiBuffer => MaterialValue::Buffer(data.to_vec()),
+        }
+    }
+}
+
+/// The decoded value of a `MaterialProperty`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum MaterialValue {
+    /// An array of single-precision floats
+    Float(Vec<f32>),
+
+    /// An array of double-precision floats
+    Double(Vec<f64>),
+
+    /// An array of integers
+    Integer(Vec<i32>),
+
+    /// A string
+    String(String),
+
+    /// A binary buffer
+    Buffer(Vec<u8>),
+}
+
+impl fmt::Display for MaterialValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MaterialValue::Float(ref values) => write!(f, "{:?}", values),
+            MaterialValue::Double(ref values) => write!(f, "{:?}", values),
+            MaterialValue::Integer(ref values) => write!(f, "{:?}", values),
+            MaterialValue::String(ref value) => write!(f, "{:?}", value),
+            MaterialValue::Buffer(ref data) =>
+                write!(f, "<{} bytes>", data.len()),
+        }
+    }
 }
 
 /// Data structure for a material
@@ -460,463 +650,350 @@ impl Material {
         unsafe { ptr_ptr_to_slice(self.properties, self.num_properties as usize) }
     }
 
-    /// Get the path of the texture
-    // TODO make a nicer interface to this information
+    /// Get the number of textures of the given type.
+    pub fn get_texture_count(&self, tex_type: TextureType) -> usize {
+        unsafe { ffi::aiGetMaterialTextureCount(self, tex_type) as usize }
+    }
+
+    /// Get the texture with the given type and index.
+    ///
+    /// Returns `None` if there is no such texture, use `get_texture_count`
+    /// to find out how many textures of a type there are.
     pub fn get_texture(&self,
                        tex_type: TextureType,
                        index: usize,
-                       ) -> Option<String> {
-        unsafe {
-            // aiGetMaterialTexture(aiMaterial: *const material::Material,
-            //                      aiTextureType: material::TextureType,
-            //                      index: c_uint,
-            //                      path: *mut types::AiString,
-            //                      mapping: *mut material::TextureMapping /*= NULL*/,
-            //                      uvindex: *mut c_uint            /*= NULL*/,
-            //                      blend: *mut c_float                    /*= NULL*/,
-            //                      op: *mut material::TextureOp           /*= NULL*/,
-            //                      mapmode: *mut material::TextureMapMode /*= NULL*/,
-            //                      flags: *mut c_uint              /*= NULL*/) -> types::Return;
-            let mut path: AiString = mem::uninitialized();
-            let res = ffi::aiGetMaterialTexture(self,
-                                 tex_type,
-                                 index as c_uint,
-                                 &mut path,
-                                 ptr::null_mut(),
-                                 ptr::null_mut(),
-                                 ptr::null_mut(),
-                                 ptr::null_mut(),
-                                 ptr::null_mut(),
-                                 ptr::null_mut(),
-                                );
-            match res {
-                Return::Success => { },
-                _ => return None,
-            }
-            path.into_string()
+                       ) -> Option<TextureSlot> {
+        let mut path = AiString::new();
+        let mut mapping: c_int = TextureMapping::Uv as c_int;
+        let mut uv_index: c_uint = 0;
+        let mut blend: c_float = 1.0;
+        let mut op: c_int = -1;
+        let mut map_mode: [c_int; 2] = [TextureMapMode::Wrap as c_int; 2];
+        let mut flags: c_uint = 0;
+
+        let res = unsafe {
+            ffi::aiGetMaterialTexture(self,
+                                      tex_type,
+                                      index as c_uint,
+                                      &mut path,
+                                      &mut mapping,
+                                      &mut uv_index,
+                                      &mut blend,
+                                      &mut op,
+                                      map_mode.as_mut_ptr(),
+                                      &mut flags)
+        };
+        match res {
+            Return::Success => { },
+            _ => return None,
         }
-    }
-}
-
-
-// #define AI_MATKEY_NAME "?mat.name",0,0
-// #define AI_MATKEY_TWOSIDED "$mat.twosided",0,0
-// #define AI_MATKEY_SHADING_MODEL "$mat.shadingm",0,0
-// #define AI_MATKEY_ENABLE_WIREFRAME "$mat.wireframe",0,0
-// #define AI_MATKEY_BLEND_FUNC "$mat.blend",0,0
-// #define AI_MATKEY_OPACITY "$mat.opacity",0,0
-// #define AI_MATKEY_BUMPSCALING "$mat.bumpscaling",0,0
-// #define AI_MATKEY_SHININESS "$mat.shininess",0,0
-// #define AI_MATKEY_REFLECTIVITY "$mat.reflectivity",0,0
-// #define AI_MATKEY_SHININESS_STRENGTH "$mat.shinpercent",0,0
-// #define AI_MATKEY_REFRACTI "$mat.refracti",0,0
-// #define AI_MATKEY_COLOR_DIFFUSE "$clr.diffuse",0,0
-// #define AI_MATKEY_COLOR_AMBIENT "$clr.ambient",0,0
-// #define AI_MATKEY_COLOR_SPECULAR "$clr.specular",0,0
-// #define AI_MATKEY_COLOR_EMISSIVE "$clr.emissive",0,0
-// #define AI_MATKEY_COLOR_TRANSPARENT "$clr.transparent",0,0
-// #define AI_MATKEY_COLOR_REFLECTIVE "$clr.reflective",0,0
-// #define AI_MATKEY_GLOBAL_BACKGROUND_IMAGE "?bg.global",0,0
-
-//     // Pure key names for all texture-related properties
-//     //! @cond MATS_DOC_FULL
-// #define _AI_MATKEY_TEXTURE_BASE          "$tex.file"
-// #define _AI_MATKEY_UVWSRC_BASE           "$tex.uvwsrc"
-// #define _AI_MATKEY_TEXOP_BASE            "$tex.op"
-// #define _AI_MATKEY_MAPPING_BASE          "$tex.mapping"
-// #define _AI_MATKEY_TEXBLEND_BASE     "$tex.blend"
-// #define _AI_MATKEY_MAPPINGMODE_U_BASE    "$tex.mapmodeu"
-// #define _AI_MATKEY_MAPPINGMODE_V_BASE    "$tex.mapmodev"
-// #define _AI_MATKEY_TEXMAP_AXIS_BASE      "$tex.mapaxis"
-// #define _AI_MATKEY_UVTRANSFORM_BASE      "$tex.uvtrafo"
-// #define _AI_MATKEY_TEXFLAGS_BASE         "$tex.flags"
-//     //! @endcond
-
-// #define AI_MATKEY_TEXTURE(type, N) _AI_MATKEY_TEXTURE_BASE,type,N
-
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_TEXTURE_DIFFUSE(N) \
-//     AI_MATKEY_TEXTURE(aiTextureType_DIFFUSE,N)
-
-// #define AI_MATKEY_TEXTURE_SPECULAR(N)    \
-//     AI_MATKEY_TEXTURE(aiTextureType_SPECULAR,N)
-
-// #define AI_MATKEY_TEXTURE_AMBIENT(N) \
-//     AI_MATKEY_TEXTURE(aiTextureType_AMBIENT,N)
-
-// #define AI_MATKEY_TEXTURE_EMISSIVE(N)    \
-//     AI_MATKEY_TEXTURE(aiTextureType_EMISSIVE,N)
-
-// #define AI_MATKEY_TEXTURE_NORMALS(N) \
-//     AI_MATKEY_TEXTURE(aiTextureType_NORMALS,N)
-
-// #define AI_MATKEY_TEXTURE_HEIGHT(N)  \
-//     AI_MATKEY_TEXTURE(aiTextureType_HEIGHT,N)
-
-// #define AI_MATKEY_TEXTURE_SHININESS(N)   \
-//     AI_MATKEY_TEXTURE(aiTextureType_SHININESS,N)
-
-// #define AI_MATKEY_TEXTURE_OPACITY(N) \
-//     AI_MATKEY_TEXTURE(aiTextureType_OPACITY,N)
-
-// #define AI_MATKEY_TEXTURE_DISPLACEMENT(N)    \
-//     AI_MATKEY_TEXTURE(aiTextureType_DISPLACEMENT,N)
-
-// #define AI_MATKEY_TEXTURE_LIGHTMAP(N)    \
-//     AI_MATKEY_TEXTURE(aiTextureType_LIGHTMAP,N)
-
-// #define AI_MATKEY_TEXTURE_REFLECTION(N)  \
-//     AI_MATKEY_TEXTURE(aiTextureType_REFLECTION,N)
-
-//     //! @endcond
-
-// #define AI_MATKEY_UVWSRC(type, N) _AI_MATKEY_UVWSRC_BASE,type,N
-
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_UVWSRC_DIFFUSE(N)  \
-//     AI_MATKEY_UVWSRC(aiTextureType_DIFFUSE,N)
-
-// #define AI_MATKEY_UVWSRC_SPECULAR(N) \
-//     AI_MATKEY_UVWSRC(aiTextureType_SPECULAR,N)
-
-// #define AI_MATKEY_UVWSRC_AMBIENT(N)  \
-//     AI_MATKEY_UVWSRC(aiTextureType_AMBIENT,N)
-
-// #define AI_MATKEY_UVWSRC_EMISSIVE(N) \
-//     AI_MATKEY_UVWSRC(aiTextureType_EMISSIVE,N)
-
-// #define AI_MATKEY_UVWSRC_NORMALS(N)  \
-//     AI_MATKEY_UVWSRC(aiTextureType_NORMALS,N)
-
-// #define AI_MATKEY_UVWSRC_HEIGHT(N)   \
-//     AI_MATKEY_UVWSRC(aiTextureType_HEIGHT,N)
-
-// #define AI_MATKEY_UVWSRC_SHININESS(N)    \
-//     AI_MATKEY_UVWSRC(aiTextureType_SHININESS,N)
-
-// #define AI_MATKEY_UVWSRC_OPACITY(N)  \
-//     AI_MATKEY_UVWSRC(aiTextureType_OPACITY,N)
-
-// #define AI_MATKEY_UVWSRC_DISPLACEMENT(N) \
-//     AI_MATKEY_UVWSRC(aiTextureType_DISPLACEMENT,N)
-
-// #define AI_MATKEY_UVWSRC_LIGHTMAP(N) \
-//     AI_MATKEY_UVWSRC(aiTextureType_LIGHTMAP,N)
-
-// #define AI_MATKEY_UVWSRC_REFLECTION(N)   \
-//     AI_MATKEY_UVWSRC(aiTextureType_REFLECTION,N)
-
-//     //! @endcond
-// #define AI_MATKEY_TEXOP(type, N) _AI_MATKEY_TEXOP_BASE,type,N
-
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_TEXOP_DIFFUSE(N)   \
-//     AI_MATKEY_TEXOP(aiTextureType_DIFFUSE,N)
-
-// #define AI_MATKEY_TEXOP_SPECULAR(N)  \
-//     AI_MATKEY_TEXOP(aiTextureType_SPECULAR,N)
-
-// #define AI_MATKEY_TEXOP_AMBIENT(N)   \
-//     AI_MATKEY_TEXOP(aiTextureType_AMBIENT,N)
-
-// #define AI_MATKEY_TEXOP_EMISSIVE(N)  \
-//     AI_MATKEY_TEXOP(aiTextureType_EMISSIVE,N)
-
-// #define AI_MATKEY_TEXOP_NORMALS(N)   \
-//     AI_MATKEY_TEXOP(aiTextureType_NORMALS,N)
-
-// #define AI_MATKEY_TEXOP_HEIGHT(N)    \
-//     AI_MATKEY_TEXOP(aiTextureType_HEIGHT,N)
-
-// #define AI_MATKEY_TEXOP_SHININESS(N) \
-//     AI_MATKEY_TEXOP(aiTextureType_SHININESS,N)
-
-// #define AI_MATKEY_TEXOP_OPACITY(N)   \
-//     AI_MATKEY_TEXOP(aiTextureType_OPACITY,N)
-
-// #define AI_MATKEY_TEXOP_DISPLACEMENT(N)  \
-//     AI_MATKEY_TEXOP(aiTextureType_DISPLACEMENT,N)
-
-// #define AI_MATKEY_TEXOP_LIGHTMAP(N)  \
-//     AI_MATKEY_TEXOP(aiTextureType_LIGHTMAP,N)
-
-// #define AI_MATKEY_TEXOP_REFLECTION(N)    \
-//     AI_MATKEY_TEXOP(aiTextureType_REFLECTION,N)
-
-//     //! @endcond
-// #define AI_MATKEY_MAPPING(type, N) _AI_MATKEY_MAPPING_BASE,type,N
-
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_MAPPING_DIFFUSE(N) \
-//     AI_MATKEY_MAPPING(aiTextureType_DIFFUSE,N)
 
-// #define AI_MATKEY_MAPPING_SPECULAR(N)    \
-//     AI_MATKEY_MAPPING(aiTextureType_SPECULAR,N)
-
-// #define AI_MATKEY_MAPPING_AMBIENT(N) \
-//     AI_MATKEY_MAPPING(aiTextureType_AMBIENT,N)
-
-// #define AI_MATKEY_MAPPING_EMISSIVE(N)    \
-//     AI_MATKEY_MAPPING(aiTextureType_EMISSIVE,N)
-
-// #define AI_MATKEY_MAPPING_NORMALS(N) \
-//     AI_MATKEY_MAPPING(aiTextureType_NORMALS,N)
-
-// #define AI_MATKEY_MAPPING_HEIGHT(N)  \
-//     AI_MATKEY_MAPPING(aiTextureType_HEIGHT,N)
-
-// #define AI_MATKEY_MAPPING_SHININESS(N)   \
-//     AI_MATKEY_MAPPING(aiTextureType_SHININESS,N)
-
-// #define AI_MATKEY_MAPPING_OPACITY(N) \
-//     AI_MATKEY_MAPPING(aiTextureType_OPACITY,N)
-
-// #define AI_MATKEY_MAPPING_DISPLACEMENT(N)    \
-//     AI_MATKEY_MAPPING(aiTextureType_DISPLACEMENT,N)
-
-// #define AI_MATKEY_MAPPING_LIGHTMAP(N)    \
-//     AI_MATKEY_MAPPING(aiTextureType_LIGHTMAP,N)
-
-// #define AI_MATKEY_MAPPING_REFLECTION(N)  \
-//     AI_MATKEY_MAPPING(aiTextureType_REFLECTION,N)
-
-//     //! @endcond
-// #define AI_MATKEY_TEXBLEND(type, N) _AI_MATKEY_TEXBLEND_BASE,type,N
-
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_TEXBLEND_DIFFUSE(N)    \
-//     AI_MATKEY_TEXBLEND(aiTextureType_DIFFUSE,N)
-
-// #define AI_MATKEY_TEXBLEND_SPECULAR(N)   \
-//     AI_MATKEY_TEXBLEND(aiTextureType_SPECULAR,N)
-
-// #define AI_MATKEY_TEXBLEND_AMBIENT(N)    \
-//     AI_MATKEY_TEXBLEND(aiTextureType_AMBIENT,N)
-
-// #define AI_MATKEY_TEXBLEND_EMISSIVE(N)   \
-//     AI_MATKEY_TEXBLEND(aiTextureType_EMISSIVE,N)
-
-// #define AI_MATKEY_TEXBLEND_NORMALS(N)    \
-//     AI_MATKEY_TEXBLEND(aiTextureType_NORMALS,N)
-
-// #define AI_MATKEY_TEXBLEND_HEIGHT(N) \
-//     AI_MATKEY_TEXBLEND(aiTextureType_HEIGHT,N)
-
-// #define AI_MATKEY_TEXBLEND_SHININESS(N)  \
-//     AI_MATKEY_TEXBLEND(aiTextureType_SHININESS,N)
-
-// #define AI_MATKEY_TEXBLEND_OPACITY(N)    \
-//     AI_MATKEY_TEXBLEND(aiTextureType_OPACITY,N)
-
-// #define AI_MATKEY_TEXBLEND_DISPLACEMENT(N)   \
-//     AI_MATKEY_TEXBLEND(aiTextureType_DISPLACEMENT,N)
-
-// #define AI_MATKEY_TEXBLEND_LIGHTMAP(N)   \
-//     AI_MATKEY_TEXBLEND(aiTextureType_LIGHTMAP,N)
-
-// #define AI_MATKEY_TEXBLEND_REFLECTION(N) \
-//     AI_MATKEY_TEXBLEND(aiTextureType_REFLECTION,N)
-
-//     //! @endcond
-// #define AI_MATKEY_MAPPINGMODE_U(type, N) _AI_MATKEY_MAPPINGMODE_U_BASE,type,N
-
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_MAPPINGMODE_U_DIFFUSE(N)   \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_DIFFUSE,N)
-
-// #define AI_MATKEY_MAPPINGMODE_U_SPECULAR(N)  \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_SPECULAR,N)
-
-// #define AI_MATKEY_MAPPINGMODE_U_AMBIENT(N)   \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_AMBIENT,N)
-
-// #define AI_MATKEY_MAPPINGMODE_U_EMISSIVE(N)  \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_EMISSIVE,N)
-
-// #define AI_MATKEY_MAPPINGMODE_U_NORMALS(N)   \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_NORMALS,N)
-
-// #define AI_MATKEY_MAPPINGMODE_U_HEIGHT(N)    \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_HEIGHT,N)
-
-// #define AI_MATKEY_MAPPINGMODE_U_SHININESS(N) \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_SHININESS,N)
-
-// #define AI_MATKEY_MAPPINGMODE_U_OPACITY(N)   \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_OPACITY,N)
-
-// #define AI_MATKEY_MAPPINGMODE_U_DISPLACEMENT(N)  \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_DISPLACEMENT,N)
-
-// #define AI_MATKEY_MAPPINGMODE_U_LIGHTMAP(N)  \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_LIGHTMAP,N)
+        let semantic = tex_type as c_uint;
+        let index = index as c_uint;
+
+        let mut trafo = [0.0; 5];
+        let uv_transform = match self.get_float_array(AI_MATKEY_TEX_UVTRANSFORM,
+                                                      semantic, index,
+                                                      &mut trafo) {
+            Some(5) => Some(UVTransform {
+                translation: Vector2D { x: trafo[0], y: trafo[1] },
+                scaling: Vector2D { x: trafo[2], y: trafo[3] },
+                rotation: trafo[4],
+            }),
+            _ => None,
+        };
+
+        let mut axis = [0.0; 3];
+        let map_axis = match self.get_float_array(AI_MATKEY_TEX_MAPAXIS,
+                                                  semantic, index,
+                                                  &mut axis) {
+            Some(3) => Some(Vector3D { x: axis[0], y: axis[1], z: axis[2] }),
+            _ => None,
+        };
+
+        let map_mode_u = TextureMapMode::from_int(map_mode[0]);
+        let map_mode_v = TextureMapMode::from_int(map_mode[1]);
+        Some(TextureSlot {
+            path: path.into_string().unwrap_or_default(),
+            mapping: TextureMapping::from_int(mapping)
+                         .unwrap_or(TextureMapping::Other),
+            uv_index: uv_index,
+            blend: blend,
+            op: TextureOp::from_int(op),
+            map_mode: [map_mode_u.unwrap_or(TextureMapMode::Wrap),
+                       map_mode_v.unwrap_or(TextureMapMode::Wrap)],
+            flags: flags,
+            uv_transform: uv_transform,
+            map_axis: map_axis,
+        })
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_U_REFLECTION(N)    \
-//     AI_MATKEY_MAPPINGMODE_U(aiTextureType_REFLECTION,N)
+    /// Get the name of the material.
+    pub fn get_name(&self) -> Option<String> {
+        self.get_string(AI_MATKEY_NAME)
+    }
 
-//     //! @endcond
-// #define AI_MATKEY_MAPPINGMODE_V(type, N) _AI_MATKEY_MAPPINGMODE_V_BASE,type,N
+    /// Check whether backface culling must be disabled for this material.
+    pub fn get_two_sided(&self) -> Option<bool> {
+        self.get_int(AI_MATKEY_TWOSIDED).map(|x| x != 0)
+    }
 
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_MAPPINGMODE_V_DIFFUSE(N)   \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_DIFFUSE,N)
+    /// Check whether the material must be rendered as a wireframe.
+    pub fn get_wireframe(&self) -> Option<bool> {
+        self.get_int(AI_MATKEY_ENABLE_WIREFRAME).map(|x| x != 0)
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_SPECULAR(N)  \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_SPECULAR,N)
+    /// Get the shading model to use for this material.
+    ///
+    /// Returns `None` if the material doesn't specify one, or if it
+    /// specifies a shading model unknown to this library.
+    pub fn get_shading_mode(&self) -> Option<ShadingMode> {
+        self.get_int(AI_MATKEY_SHADING_MODEL).and_then(ShadingMode::from_int)
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_AMBIENT(N)   \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_AMBIENT,N)
+    /// Get the blend function to use for this material.
+    pub fn get_blend_mode(&self) -> Option<BlendMode> {
+        match self.get_int(AI_MATKEY_BLEND_FUNC) {
+            Some(0) => Some(BlendMode::Default),
+            Some(1) => Some(BlendMode::Additive),
+            _ => None,
+        }
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_EMISSIVE(N)  \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_EMISSIVE,N)
+    /// Get the opacity of the material in [0..1].
+    pub fn get_opacity(&self) -> Option<f32> {
+        self.get_float(AI_MATKEY_OPACITY)
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_NORMALS(N)   \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_NORMALS,N)
+    /// Get the scaling factor for bump maps.
+    pub fn get_bump_scaling(&self) -> Option<f32> {
+        self.get_float(AI_MATKEY_BUMPSCALING)
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_HEIGHT(N)    \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_HEIGHT,N)
+    /// Get the exponent of the Phong specular equation.
+    pub fn get_shininess(&self) -> Option<f32> {
+        self.get_float(AI_MATKEY_SHININESS)
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_SHININESS(N) \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_SHININESS,N)
+    /// Get the scaling factor for the specular color.
+    pub fn get_shininess_strength(&self) -> Option<f32> {
+        self.get_float(AI_MATKEY_SHININESS_STRENGTH)
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_OPACITY(N)   \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_OPACITY,N)
+    /// Get the scaling factor for the reflective color.
+    pub fn get_reflectivity(&self) -> Option<f32> {
+        self.get_float(AI_MATKEY_REFLECTIVITY)
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_DISPLACEMENT(N)  \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_DISPLACEMENT,N)
+    /// Get the index of refraction of the material.
+    pub fn get_refraction_index(&self) -> Option<f32> {
+        self.get_float(AI_MATKEY_REFRACTI)
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_LIGHTMAP(N)  \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_LIGHTMAP,N)
+    /// Get the diffuse color of the material.
+    pub fn get_diffuse_color(&self) -> Option<Color4D> {
+        self.get_color(AI_MATKEY_COLOR_DIFFUSE)
+    }
 
-// #define AI_MATKEY_MAPPINGMODE_V_REFLECTION(N)    \
-//     AI_MATKEY_MAPPINGMODE_V(aiTextureType_REFLECTION,N)
+    /// Get the ambient color of the material.
+    pub fn get_ambient_color(&self) -> Option<Color4D> {
+        self.get_color(AI_MATKEY_COLOR_AMBIENT)
+    }
 
-//     //! @endcond
-// #define AI_MATKEY_TEXMAP_AXIS(type, N) _AI_MATKEY_TEXMAP_AXIS_BASE,type,N
+    /// Get the specular color of the material.
+    pub fn get_specular_color(&self) -> Option<Color4D> {
+        self.get_color(AI_MATKEY_COLOR_SPECULAR)
+    }
 
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_TEXMAP_AXIS_DIFFUSE(N) \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_DIFFUSE,N)
+    /// Get the emissive color of the material.
+    pub fn get_emissive_color(&self) -> Option<Color4D> {
+        self.get_color(AI_MATKEY_COLOR_EMISSIVE)
+    }
 
-// #define AI_MATKEY_TEXMAP_AXIS_SPECULAR(N)    \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_SPECULAR,N)
+    /// Get the color of transparent objects behind the material.
+    pub fn get_transparent_color(&self) -> Option<Color4D> {
+        self.get_color(AI_MATKEY_COLOR_TRANSPARENT)
+    }
 
-// #define AI_MATKEY_TEXMAP_AXIS_AMBIENT(N) \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_AMBIENT,N)
+    /// Get the color of reflections on the material.
+    pub fn get_reflective_color(&self) -> Option<Color4D> {
+        self.get_color(AI_MATKEY_COLOR_REFLECTIVE)
+    }
 
-// #define AI_MATKEY_TEXMAP_AXIS_EMISSIVE(N)    \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_EMISSIVE,N)
+    /// Get the float property with the given key, one of the
+    /// `AI_MATKEY_XXX` constants.
+    ///
+    /// Integer properties are converted automatically.
+    pub fn get_float(&self, key: &str) -> Option<f32> {
+        let mut out = [0.0];
+        self.get_float_array(key, 0, 0, &mut out).map(|_| out[0])
+    }
 
-// #define AI_MATKEY_TEXMAP_AXIS_NORMALS(N) \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_NORMALS,N)
+    /// Read the float array property with the given key into `out`.
+    ///
+    /// `semantic` and `index` select the texture for `AI_MATKEY_TEX_XXX`
+    /// keys, they are 0 for all other keys. Returns the number of values
+    /// read.
+    pub fn get_float_array(&self,
+                           key: &str,
+                           semantic: u32,
+                           index: u32,
+                           out: &mut [f32]) -> Option<usize> {
+        let ckey = CString::new(key).ok()?;
+        let mut max = out.len() as c_uint;
+        let res = unsafe {
+            ffi::aiGetMaterialFloatArray(self, ckey.as_ptr(), semantic, index,
+                                         out.as_mut_ptr(), &mut max)
+        };
+        match res {
+            Return::Success => Some(max as usize),
+            _ => None,
+        }
+    }
 
-// #define AI_MATKEY_TEXMAP_AXIS_HEIGHT(N)  \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_HEIGHT,N)
+    /// Get the integer property with the given key, one of the
+    /// `AI_MATKEY_XXX` constants.
+    ///
+    /// Float properties are converted automatically.
+    pub fn get_int(&self, key: &str) -> Option<i32> {
+        let mut out: c_int = 0;
+        let ckey = CString::new(key).ok()?;
+        let res = unsafe {
+            ffi::aiGetMaterialIntegerArray(self, ckey.as_ptr(), 0, 0,
+                                           &mut out, ptr::null_mut())
+        };
+        match res {
+            Return::Success => Some(out),
+            _ => None,
+        }
+    }
 
-// #define AI_MATKEY_TEXMAP_AXIS_SHININESS(N)   \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_SHININESS,N)
+    /// Get the color property with the given key, one of the
+    /// `AI_MATKEY_COLOR_XXX` constants.
+    ///
+    /// Colors stored without an alpha component get an alpha of 1.
+    pub fn get_color(&self, key: &str) -> Option<Color4D> {
+        let mut out = Color4D { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
+        let ckey = CString::new(key).ok()?;
+        let res = unsafe {
+            ffi::aiGetMaterialColor(self, ckey.as_ptr(), 0, 0, &mut out)
+        };
+        match res {
+            Return::Success => Some(out),
+            _ => None,
+        }
+    }
 
-// #define AI_MATKEY_TEXMAP_AXIS_OPACITY(N) \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_OPACITY,N)
+    /// Get the string property with the given key, one of the
+    /// `AI_MATKEY_XXX` constants.
+    pub fn get_string(&self, key: &str) -> Option<String> {
+        let mut out = AiString::new();
+        let ckey = CString::new(key).ok()?;
+        let res = unsafe {
+            ffi::aiGetMaterialString(self, ckey.as_ptr(), 0, 0, &mut out)
+        };
+        match res {
+            Return::Success => out.into_string(),
+            _ => None,
+        }
+    }
+}
 
-// #define AI_MATKEY_TEXMAP_AXIS_DISPLACEMENT(N)    \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_DISPLACEMENT,N)
 
-// #define AI_MATKEY_TEXMAP_AXIS_LIGHTMAP(N)    \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_LIGHTMAP,N)
+/// Material key: the name of the material, if available (string).
+///
+/// Ignored by `Process::RemoveRedundantMaterials`. Materials are considered
+/// equal even if their names are different.
+pub const AI_MATKEY_NAME: &'static str = "?mat.name";
 
-// #define AI_MATKEY_TEXMAP_AXIS_REFLECTION(N)  \
-//     AI_MATKEY_TEXMAP_AXIS(aiTextureType_REFLECTION,N)
+/// Material key: whether backface culling must be disabled for the material
+/// (integer, 0 or 1)
+pub const AI_MATKEY_TWOSIDED: &'static str = "$mat.twosided";
 
-//     //! @endcond
-// #define AI_MATKEY_UVTRANSFORM(type, N) _AI_MATKEY_UVTRANSFORM_BASE,type,N
+/// Material key: one of the `ShadingMode` values (integer)
+pub const AI_MATKEY_SHADING_MODEL: &'static str = "$mat.shadingm";
 
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_UVTRANSFORM_DIFFUSE(N) \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_DIFFUSE,N)
+/// Material key: whether the material must be rendered as a wireframe
+/// (integer, 0 or 1)
+pub const AI_MATKEY_ENABLE_WIREFRAME: &'static str = "$mat.wireframe";
 
-// #define AI_MATKEY_UVTRANSFORM_SPECULAR(N)    \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_SPECULAR,N)
+/// Material key: one of the `BlendMode` values (integer)
+pub const AI_MATKEY_BLEND_FUNC: &'static str = "$mat.blend";
 
-// #define AI_MATKEY_UVTRANSFORM_AMBIENT(N) \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_AMBIENT,N)
+/// Material key: the opacity of the material in [0..1] (float)
+pub const AI_MATKEY_OPACITY: &'static str = "$mat.opacity";
 
-// #define AI_MATKEY_UVTRANSFORM_EMISSIVE(N)    \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_EMISSIVE,N)
+/// Material key: scaling factor for bump maps (float)
+pub const AI_MATKEY_BUMPSCALING: &'static str = "$mat.bumpscaling";
 
-// #define AI_MATKEY_UVTRANSFORM_NORMALS(N) \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_NORMALS,N)
+/// Material key: the exponent of the Phong specular equation (float)
+pub const AI_MATKEY_SHININESS: &'static str = "$mat.shininess";
 
-// #define AI_MATKEY_UVTRANSFORM_HEIGHT(N)  \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_HEIGHT,N)
+/// Material key: scaling factor for the reflective color (float)
+pub const AI_MATKEY_REFLECTIVITY: &'static str = "$mat.reflectivity";
 
-// #define AI_MATKEY_UVTRANSFORM_SHININESS(N)   \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_SHININESS,N)
+/// Material key: scaling factor for the specular color (float)
+pub const AI_MATKEY_SHININESS_STRENGTH: &'static str = "$mat.shinpercent";
 
-// #define AI_MATKEY_UVTRANSFORM_OPACITY(N) \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_OPACITY,N)
+/// Material key: the index of refraction of the material (float)
+pub const AI_MATKEY_REFRACTI: &'static str = "$mat.refracti";
 
-// #define AI_MATKEY_UVTRANSFORM_DISPLACEMENT(N)    \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_DISPLACEMENT,N)
+/// Material key: the diffuse color of the material (color)
+pub const AI_MATKEY_COLOR_DIFFUSE: &'static str = "$clr.diffuse";
 
-// #define AI_MATKEY_UVTRANSFORM_LIGHTMAP(N)    \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_LIGHTMAP,N)
+/// Material key: the ambient color of the material (color)
+pub const AI_MATKEY_COLOR_AMBIENT: &'static str = "$clr.ambient";
 
-// #define AI_MATKEY_UVTRANSFORM_REFLECTION(N)  \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_REFLECTION,N)
+/// Material key: the specular color of the material (color)
+pub const AI_MATKEY_COLOR_SPECULAR: &'static str = "$clr.specular";
 
-// #define AI_MATKEY_UVTRANSFORM_UNKNOWN(N) \
-//     AI_MATKEY_UVTRANSFORM(aiTextureType_UNKNOWN,N)
+/// Material key: the emissive color of the material (color)
+pub const AI_MATKEY_COLOR_EMISSIVE: &'static str = "$clr.emissive";
 
-//     //! @endcond
-// #define AI_MATKEY_TEXFLAGS(type, N) _AI_MATKEY_TEXFLAGS_BASE,type,N
+/// Material key: the color of transparent objects behind the material
+/// (color)
+pub const AI_MATKEY_COLOR_TRANSPARENT: &'static str = "$clr.transparent";
 
-//     // For backward compatibility and simplicity
-//     //! @cond MATS_DOC_FULL
-// #define AI_MATKEY_TEXFLAGS_DIFFUSE(N)    \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_DIFFUSE,N)
+/// Material key: the color of reflections on the material (color)
+pub const AI_MATKEY_COLOR_REFLECTIVE: &'static str = "$clr.reflective";
 
-// #define AI_MATKEY_TEXFLAGS_SPECULAR(N)   \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_SPECULAR,N)
+/// Material key: the path of an image file used as the global background
+/// (string)
+pub const AI_MATKEY_GLOBAL_BACKGROUND_IMAGE: &'static str = "?bg.global";
 
-// #define AI_MATKEY_TEXFLAGS_AMBIENT(N)    \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_AMBIENT,N)
+/// Texture key: the path of the texture (string).
+///
+/// Like all `AI_MATKEY_TEX_XXX` keys this is queried together with the
+/// texture type and the index of the texture.
+pub const AI_MATKEY_TEX_FILE: &'static str = "$tex.file";
 
-// #define AI_MATKEY_TEXFLAGS_EMISSIVE(N)   \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_EMISSIVE,N)
+/// Texture key: the index of the UV channel of UV mapped textures
+/// (integer)
+pub const AI_MATKEY_TEX_UVWSRC: &'static str = "$tex.uvwsrc";
 
-// #define AI_MATKEY_TEXFLAGS_NORMALS(N)    \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_NORMALS,N)
+/// Texture key: one of the `TextureOp` values (integer)
+pub const AI_MATKEY_TEX_OP: &'static str = "$tex.op";
 
-// #define AI_MATKEY_TEXFLAGS_HEIGHT(N) \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_HEIGHT,N)
+/// Texture key: one of the `TextureMapping` values (integer)
+pub const AI_MATKEY_TEX_MAPPING: &'static str = "$tex.mapping";
 
-// #define AI_MATKEY_TEXFLAGS_SHININESS(N)  \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_SHININESS,N)
+/// Texture key: the strength of the texture in the blend equation (float)
+pub const AI_MATKEY_TEX_BLEND: &'static str = "$tex.blend";
 
-// #define AI_MATKEY_TEXFLAGS_OPACITY(N)    \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_OPACITY,N)
+/// Texture key: the `TextureMapMode` for the u axis (integer)
+pub const AI_MATKEY_TEX_MAPPINGMODE_U: &'static str = "$tex.mapmodeu";
 
-// #define AI_MATKEY_TEXFLAGS_DISPLACEMENT(N)   \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_DISPLACEMENT,N)
+/// Texture key: the `TextureMapMode` for the v axis (integer)
+pub const AI_MATKEY_TEX_MAPPINGMODE_V: &'static str = "$tex.mapmodev";
 
-// #define AI_MATKEY_TEXFLAGS_LIGHTMAP(N)   \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_LIGHTMAP,N)
+/// Texture key: the main axis of non-UV mappings (`Vector3D`)
+pub const AI_MATKEY_TEX_MAPAXIS: &'static str = "$tex.mapaxis";
 
-// #define AI_MATKEY_TEXFLAGS_REFLECTION(N) \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_REFLECTION,N)
+/// Texture key: the transformation of the UV coordinates (`UVTransform`)
+pub const AI_MATKEY_TEX_UVTRANSFORM: &'static str = "$tex.uvtrafo";
 
-// #define AI_MATKEY_TEXFLAGS_UNKNOWN(N)    \
-//     AI_MATKEY_TEXFLAGS(aiTextureType_UNKNOWN,N)
+/// Texture key: any combination of the `TextureFlags` (integer)
+pub const AI_MATKEY_TEX_FLAGS: &'static str = "$tex.flags";
 
 //         /** @brief Retrieve a material property with a specific key from the material
 //          *
@@ -934,88 +1011,74 @@ impl Material {
 //         unsigned int  index,
 //         const C_STRUCT aiMaterialProperty** pPropOut);
 
-//         /** @brief Retrieve an array of float values with a specific key
-//          *  from the material
-//          *
-//          * Pass one of the AI_MATKEY_XXX constants for the last three parameters (the
-//          * example reads the #AI_MATKEY_UVTRANSFORM property of the first diffuse texture)
-//          * @code
-//          * aiUVTransform trafo;
-//          * unsigned int max = sizeof(aiUVTransform);
-//          * if (AI_SUCCESS != aiGetMaterialFloatArray(mat, AI_MATKEY_UVTRANSFORM(aiTextureType_DIFFUSE,0),
-//          *    (float*)&trafo, &max) || sizeof(aiUVTransform) != max)
-//          * {
-//          *   // error handling
-//          * }
-//          * @endcode
-//          *
-//          * @param pMat Pointer to the input material. May not be NULL
-//          * @param pKey Key to search for. One of the AI_MATKEY_XXX constants.
-//          * @param pOut Pointer to a buffer to receive the result.
-//          * @param pMax Specifies the size of the given buffer, in float's.
-//          *        Receives the number of values (not bytes!) read.
-//          * @param type (see the code sample above)
-//          * @param index (see the code sample above)
-//          * @return Specifies whether the key has been found. If not, the output
-//          *   arrays remains unmodified and pMax is set to 0.*/
-//     ASSIMP_API C_ENUM aiReturn aiGetMaterialFloatArray(
-//         const C_STRUCT aiMaterial* pMat,
-//         const char* pKey,
-//         unsigned int type,
-//         unsigned int index,
-//         float* pOut,
-//         unsigned int* pMax);
-
-
-//     // Use our friend, the C preprocessor
-// #define aiGetMaterialFloat (pMat, type, index, pKey, pOut) \
-//     aiGetMaterialFloatArray(pMat, type, index, pKey, pOut, NULL)
-
-
-//         /** @brief Retrieve an array of integer values with a specific key
-//          *  from a material
-//          *
-//          * See the sample for aiGetMaterialFloatArray for more information.*/
-//     ASSIMP_API C_ENUM aiReturn aiGetMaterialIntegerArray(const C_STRUCT aiMaterial* pMat,
-//                                                          const char* pKey,
-//                                                          unsigned int  type,
-//                                                          unsigned int  index,
-//                                                          int* pOut,
-//                                                          unsigned int* pMax);
-
-
-//     // use our friend, the C preprocessor
-// #define aiGetMaterialInteger (pMat, type, index, pKey, pOut) \
-//     aiGetMaterialIntegerArray(pMat, type, index, pKey, pOut, NULL)
-
-
-
-//         /** @brief Retrieve a color value from the material property table
-//          *
-//          * See the sample for aiGetMaterialFloat for more information*/
-//     ASSIMP_API C_ENUM aiReturn aiGetMaterialColor(const C_STRUCT aiMaterial* pMat,
-//                                                   const char* pKey,
-//                                                   unsigned int type,
-//                                                   unsigned int index,
-//                                                   C_STRUCT aiColor4D* pOut);
-
+#[cfg(test)]
+mod test {
+    use super::{BlendMode, ShadingMode, TextureMapMode, TextureMapping,
+                TextureType, MaterialValue};
+    use super::{AI_MATKEY_NAME, AI_MATKEY_SHININESS, AI_MATKEY_SHADING_MODEL};
+    use fixtures::{import_obj, find_material, OBJ};
+
+    static MTL: &'static str = "newmtl red
+Kd 1 0 0
+Ks 0.5 0.5 0.5
+Ns 32
+d 0.25
+illum 2
+";
+
+    #[test]
+    fn test_material_accessors() {
+        let scene = import_obj(OBJ, MTL);
+        let material = find_material(&scene, "red");
+
+        let diffuse = material.get_diffuse_color().unwrap();
+        assert_eq!((diffuse.r, diffuse.g, diffuse.b), (1.0, 0.0, 0.0));
+        let specular = material.get_specular_color().unwrap();
+        assert_eq!((specular.r, specular.g, specular.b), (0.5, 0.5, 0.5));
+        assert_eq!(material.get_shininess(), Some(32.0));
+        assert_eq!(material.get_opacity(), Some(0.25));
+        assert_eq!(material.get_shading_mode(), Some(ShadingMode::Phong));
+        assert!(material.get_blend_mode().is_none() ||
+                material.get_blend_mode() == Some(BlendMode::Default));
+        assert_eq!(material.get_float("$no.such.key"), None);
+    }
 
-//         /** @brief Retrieve a string from the material property table
-//          *
-//          * See the sample for aiGetMaterialFloat for more information.*/
-//     ASSIMP_API C_ENUM aiReturn aiGetMaterialString(const C_STRUCT aiMaterial* pMat,
-//                                                    const char* pKey,
-//                                                    unsigned int type,
-//                                                    unsigned int index,
-//                                                    C_STRUCT AiString* pOut);
-
-//         /** Get the number of textures for a particular texture type.
-//          *  @param[in] pMat Pointer to the input material. May not be NULL
-//          *  @param type Texture type to check for
-//          *  @return Number of textures for this type.
-//          *  @note A texture can be easily queried using #aiGetMaterialTexture() */
-//     ASSIMP_API unsigned int aiGetMaterialTextureCount(const C_STRUCT aiMaterial* pMat,
-//                                                       C_ENUM aiTextureType type);
+    #[test]
+    fn test_property_values() {
+        let scene = import_obj(OBJ, MTL);
+        let material = find_material(&scene, "red");
+
+        let value = |key: &str| {
+            material.get_properties().iter()
+                .find(|prop| prop.key.as_str().ok() == Some(key))
+                .map(|prop| prop.get_value())
+        };
+        assert_eq!(value(AI_MATKEY_NAME),
+                   Some(MaterialValue::String("red".to_string())));
+        assert_eq!(value(AI_MATKEY_SHININESS),
+                   Some(MaterialValue::Float(vec![32.0])));
+        assert_eq!(value(AI_MATKEY_SHADING_MODEL),
+                   Some(MaterialValue::Integer(vec![3])));
+    }
 
+    #[test]
+    fn test_texture_slots() {
+        let mtl = format!("{}map_Kd -clamp on diffuse.png\n", MTL);
+        let scene = import_obj(OBJ, &mtl);
+        let material = scene.get_materials().iter()
+            .find(|mat| mat.get_texture_count(TextureType::Diffuse) > 0)
+            .expect("material not found");
+
+        assert_eq!(material.get_texture_count(TextureType::Diffuse), 1);
+        assert_eq!(material.get_texture_count(TextureType::Normals), 0);
+        assert!(material.get_texture(TextureType::Diffuse, 1).is_none());
+
+        let slot = material.get_texture(TextureType::Diffuse, 0).unwrap();
+        assert_eq!(slot.path, "diffuse.png");
+        assert_eq!(slot.mapping, TextureMapping::Uv);
+        assert_eq!(slot.uv_index, 0);
+        assert_eq!(slot.map_mode, [TextureMapMode::Clamp; 2]);
+    }
+}
 
 // vim: et tw=78 sw=4:
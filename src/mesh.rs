@@ -2,9 +2,14 @@
 
 use libc::{c_uint, c_float};
 use std::fmt;
+use std::ops::BitOr;
+use std::slice;
 
-use types::{Vector3D, Color4D, Matrix4x4, AiString};
+use types::{Vector3D, Color4D, Matrix4x4, AiString, Aabb,
+            BoundingSphere};
 use util::{ptr_ptr_to_slice, ptr_to_slice};
+use vertex::{VertexBuffer, VertexBufferBuilder, VertexBufferError,
+             VertexLayout};
 
 use mesh::PrimitiveType::{Point, Line, Triangle, Polygon};
 
@@ -133,6 +138,211 @@ impl PrimitiveType {
     }
 }
 
+/// A set of primitive types, such as `Mesh::get_primitive_types` returns.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PrimitiveTypes {
+    bits: u32,
+}
+
+impl PrimitiveTypes {
+    /// Create an empty set
+    pub fn empty() -> PrimitiveTypes {
+        PrimitiveTypes { bits: 0 }
+    }
+
+    /// Create a set containing all primitive types
+    pub fn all() -> PrimitiveTypes {
+        PrimitiveTypes::from_bits(0xf)
+    }
+
+    /// Create a set from a bitwise combination of `PrimitiveType` values.
+    /// Unknown bits are dropped.
+    pub fn from_bits(bits: u32) -> PrimitiveTypes {
+        PrimitiveTypes { bits: bits & 0xf }
+    }
+
+    /// Get the set as a bitwise combination of `PrimitiveType` values
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Check whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Check whether the set contains `prim`
+    pub fn contains(&self, prim: PrimitiveType) -> bool {
+        self.bits & prim as u32 != 0
+    }
+
+    /// Add `prim` to the set
+    pub fn insert(&mut self, prim: PrimitiveType) {
+        self.bits |= prim as u32;
+    }
+
+    /// Remove `prim` from the set
+    pub fn remove(&mut self, prim: PrimitiveType) {
+        self.bits &= !(prim as u32);
+    }
+
+    /// Iterate over the primitive types in the set, from points to polygons
+    pub fn iter(&self) -> impl Iterator<Item=PrimitiveType> {
+        let set = *self;
+        [Point, Line, Triangle, Polygon].iter()
+            .cloned()
+            .filter(move |&prim| set.contains(prim))
+    }
+}
+
+impl From<PrimitiveType> for PrimitiveTypes {
+    fn from(prim: PrimitiveType) -> PrimitiveTypes {
+        PrimitiveTypes { bits: prim as u32 }
+    }
+}
+
+impl BitOr for PrimitiveTypes {
+    type Output = PrimitiveTypes;
+
+    fn bitor(self, rhs: PrimitiveTypes) -> PrimitiveTypes {
+        PrimitiveTypes { bits: self.bits | rhs.bits }
+    }
+}
+
+impl BitOr<PrimitiveType> for PrimitiveTypes {
+    type Output = PrimitiveTypes;
+
+    fn bitor(self, rhs: PrimitiveType) -> PrimitiveTypes {
+        PrimitiveTypes { bits: self.bits | rhs as u32 }
+    }
+}
+
+impl BitOr for PrimitiveType {
+    type Output = PrimitiveTypes;
+
+    fn bitor(self, rhs: PrimitiveType) -> PrimitiveTypes {
+        PrimitiveTypes::from(self) | rhs
+    }
+}
+
+impl fmt::Debug for PrimitiveTypes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// A single face of a mesh, with the positions of its vertices.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Primitive<'a> {
+    /// A point, with its vertex index and position
+    Point(u32, Vector3D),
+
+    /// A line, with its vertex indices and positions
+    Line([u32; 2], [Vector3D; 2]),
+
+    /// A triangle, with its vertex indices and positions
+    Triangle([u32; 3], [Vector3D; 3]),
+
+    /// A polygon with more than three vertices, with its vertex indices.
+    ///
+    /// Unlike the other variants it doesn't carry the vertex positions, as
+    /// a polygon can have any number of vertices and collecting them would
+    /// allocate for every face. Look them up with `Mesh::get_vertices`, all
+    /// indices are known to be valid.
+    Polygon(&'a [u32]),
+}
+
+impl<'a> Primitive<'a> {
+    /// Get the type of the primitive
+    pub fn get_type(&self) -> PrimitiveType {
+        match *self {
+            Primitive::Point(..) => Point,
+            Primitive::Line(..) => Line,
+            Primitive::Triangle(..) => Triangle,
+            Primitive::Polygon(_) => Polygon,
+        }
+    }
+
+    /// Get the vertex indices of the primitive
+    pub fn get_indices(&self) -> &[u32] {
+        match *self {
+            Primitive::Point(ref index, _) => slice::from_ref(index),
+            Primitive::Line(ref indices, _) => indices,
+            Primitive::Triangle(ref indices, _) => indices,
+            Primitive::Polygon(indices) => indices,
+        }
+    }
+}
+
+/// Iterates over the faces of a mesh as typed primitives.
+///
+/// Created by `Mesh::primitives`. Faces referring to a vertex the mesh
+/// doesn't have are skipped, `get_skipped` counts them.
+pub struct Primitives<'a> {
+    faces: slice::Iter<'a, Face>,
+    vertices: &'a [Vector3D],
+    skipped: usize,
+}
+
+impl<'a> Primitives<'a> {
+    /// Get the number of faces skipped so far because they refer to a
+    /// vertex the mesh doesn't have. Only malformed files contain such
+    /// faces.
+    pub fn get_skipped(&self) -> usize {
+        self.skipped
+    }
+}
+
+impl<'a> Iterator for Primitives<'a> {
+    type Item = Primitive<'a>;
+
+    fn next(&mut self) -> Option<Primitive<'a>> {
+        loop {
+            let indices = self.faces.next()?.get_indices();
+            let count = self.vertices.len();
+            if indices.iter().any(|&i| i as usize >= count) {
+                self.skipped += 1;
+                continue;
+            }
+            let vertices = self.vertices;
+            let pos = |i: u32| vertices[i as usize];
+            let prim = match *indices {
+                [a] => Primitive::Point(a, pos(a)),
+                [a, b] => Primitive::Line([a, b], [pos(a), pos(b)]),
+                [a, b, c] =>
+                    Primitive::Triangle([a, b, c], [pos(a), pos(b), pos(c)]),
+                _ => Primitive::Polygon(indices),
+            };
+            return Some(prim);
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.faces.size_hint().1)
+    }
+}
+
+/// Iterates over the triangles of a mesh, skipping all other faces.
+///
+/// Created by `Mesh::triangles`.
+pub struct Triangles<'a> {
+    primitives: Primitives<'a>,
+}
+
+impl<'a> Iterator for Triangles<'a> {
+    type Item = ([u32; 3], [Vector3D; 3]);
+
+    fn next(&mut self) -> Option<([u32; 3], [Vector3D; 3])> {
+        loop {
+            match self.primitives.next()? {
+                Primitive::Triangle(indices, positions) =>
+                    return Some((indices, positions)),
+                _ => continue,
+            }
+        }
+    }
+}
+
 /// NOT CURRENTLY IN USE. An AnimMesh is an attachment to a Mesh that stores per-vertex
 /// animations for a particular frame.
 ///
@@ -420,7 +630,36 @@ pub struct Mesh {
 impl Mesh {
     /// Check if the mesh has a given primitive type
     pub fn has_primitive(&self, prim: PrimitiveType) -> bool {
-        (self.primitive_types & prim as u32) != 0
+        self.get_primitive_types().contains(prim)
+    }
+
+    /// Get the types of primitives present in the mesh.
+    pub fn get_primitive_types(&self) -> PrimitiveTypes {
+        PrimitiveTypes::from_bits(self.primitive_types)
+    }
+
+    /// Iterate over the faces of the mesh as typed primitives.
+    ///
+    /// Faces with a single index are points, those with two lines and those
+    /// with three triangles. Faces with more indices are polygons. Faces
+    /// referring to a vertex the mesh doesn't have are skipped.
+    pub fn primitives(&self) -> Primitives<'_> {
+        Primitives {
+            faces: self.get_faces().iter(),
+            vertices: self.get_vertices(),
+            skipped: 0,
+        }
+    }
+
+    /// Iterate over the triangles of the mesh, skipping all points, lines
+    /// and polygons.
+    ///
+    /// Use `Process::Triangulate` when importing to turn polygons into
+    /// triangles.
+    pub fn triangles(&self) -> Triangles<'_> {
+        Triangles {
+            primitives: self.primitives(),
+        }
     }
 
     /// Vertex positions.
@@ -550,16 +789,35 @@ impl Mesh {
     pub fn get_bones(&self) -> &[&Bone] {
         unsafe { ptr_ptr_to_slice(self.bones, self.num_bones as usize) }
     }
+
+    /// Get the bounding box of the vertex positions.
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_points(self.get_vertices())
+    }
+
+    /// Get a sphere containing all vertex positions.
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere::from_points(self.get_vertices())
+    }
+
+    /// Pack the mesh into an interleaved vertex buffer with the given
+    /// layout, and its triangles into an index buffer.
+    ///
+    /// This uses the default settings of `VertexBufferBuilder`, use the
+    /// builder directly to change them.
+    pub fn to_vertex_buffer(&self, layout: &VertexLayout)
+                            -> Result<VertexBuffer, VertexBufferError> {
+        VertexBufferBuilder::new(layout.clone()).build(self)
+    }
 }
 
 impl fmt::Display for Mesh {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        try!(write!(f, "Mesh {{ name: {}, primitive_types:", self.name,));
+        write!(f, "Mesh {{ name: {}, primitive_types:", self.name,)?;
 
-        if self.has_primitive(Point) { try!(write!(f, " Point")) }
-        if self.has_primitive(Line) { try!(write!(f, " Line")) }
-        if self.has_primitive(Triangle) { try!(write!(f, " Triangle")) }
-        if self.has_primitive(Polygon) { try!(write!(f, " Polygon")) }
+        for prim in self.get_primitive_types().iter() {
+            write!(f, " {:?}", prim)?;
+        }
 
         write!(f, ", num_vertices: {}, num_faces: {}, num_bones: {}, material_index: {} }}",
         self.num_vertices,
@@ -569,4 +827,69 @@ impl fmt::Display for Mesh {
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::{Face, Primitive, Primitives, PrimitiveTypes};
+    use types::Vector3D;
+    use super::PrimitiveType::{Point, Triangle, Polygon};
+    use importer::Importer;
+
+    #[test]
+    fn test_primitives() {
+        let importer = Importer::new();
+        let scene = importer.import_from_memory(b"v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+f 1 2 3
+f 1 2 3 4
+", "obj").unwrap();
+        let mesh = scene.get_meshes()[0];
+
+        let types = mesh.get_primitive_types();
+        assert!(types.contains(Triangle) && types.contains(Polygon));
+        assert!(!types.contains(Point));
+        assert_eq!(types.iter().collect::<Vec<_>>(), vec![Triangle, Polygon]);
+        assert_eq!(Triangle | Polygon, types);
+        assert_eq!(PrimitiveTypes::from_bits(0x14), Triangle.into());
+
+        let prims: Vec<_> = mesh.primitives().collect();
+        assert_eq!(prims.len(), 2);
+        match prims[0] {
+            Primitive::Triangle(indices, positions) => {
+                let p = positions[1];
+                assert_eq!(mesh.get_vertices()[indices[1] as usize], p);
+            }
+            _ => panic!("expected a triangle"),
+        }
+        assert_eq!(prims[1].get_type(), Polygon);
+        assert_eq!(prims[1].get_indices().len(), 4);
+        assert_eq!(mesh.triangles().count(), 1);
+    }
+
+    #[test]
+    fn test_invalid_face() {
+        let vertices = [Vector3D { x: 0.0, y: 0.0, z: 0.0 },
+                        Vector3D { x: 1.0, y: 0.0, z: 0.0 }];
+        let mut valid = [0, 1];
+        let mut invalid = [0, 1, 2];
+        let faces = [
+            Face { num_indices: 3, indices: invalid.as_mut_ptr() },
+            Face { num_indices: 2, indices: valid.as_mut_ptr() },
+        ];
+        let mut prims = Primitives {
+            faces: faces.iter(),
+            vertices: &vertices,
+            skipped: 0,
+        };
+
+        match prims.next() {
+            Some(Primitive::Line(indices, _)) => assert_eq!(indices, [0, 1]),
+            _ => panic!("expected a line"),
+        }
+        assert!(prims.next().is_none());
+        assert_eq!(prims.get_skipped(), 1);
+    }
+}
+
 // vim: et tw=78 sw=4:
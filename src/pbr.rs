@@ -0,0 +1,261 @@
+//! Conversion of materials to a metallic-roughness PBR model
+//!
+//! Formats designed for physically based rendering (e.g. glTF 2.0) store the
+//! PBR parameters directly, these are used when present. All other materials
+//! are converted from their Phong parameters using the heuristic described
+//! on `PbrMaterial::from_material`.
+
+use material::{Material, TextureSlot, TextureType};
+use material::{AI_MATKEY_COLOR_DIFFUSE, AI_MATKEY_COLOR_EMISSIVE,
+               AI_MATKEY_COLOR_SPECULAR};
+use types::{Color3D, Color4D};
+
+/// Material key: the base color of glTF materials (color)
+pub const AI_MATKEY_GLTF_BASE_COLOR_FACTOR: &'static str =
+    "$mat.gltf.pbrMetallicRoughness.baseColorFactor";
+
+/// Material key: the metalness of glTF materials in [0..1] (float)
+pub const AI_MATKEY_GLTF_METALLIC_FACTOR: &'static str =
+    "$mat.gltf.pbrMetallicRoughness.metallicFactor";
+
+/// Material key: the roughness of glTF materials in [0..1] (float)
+pub const AI_MATKEY_GLTF_ROUGHNESS_FACTOR: &'static str =
+    "$mat.gltf.pbrMetallicRoughness.roughnessFactor";
+
+/// Material key: the base color used by newer versions of assimp (color)
+pub const AI_MATKEY_BASE_COLOR: &'static str = "$clr.base";
+
+/// Material key: the metalness used by newer versions of assimp (float)
+pub const AI_MATKEY_METALLIC_FACTOR: &'static str = "$mat.metallicFactor";
+
+/// Material key: the roughness used by newer versions of assimp (float)
+pub const AI_MATKEY_ROUGHNESS_FACTOR: &'static str = "$mat.roughnessFactor";
+
+/// A material in the metallic-roughness model.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PbrMaterial {
+    /// The base color of the material, including its opacity.
+    pub base_color: Color4D,
+
+    /// The metalness of the material in [0..1].
+    pub metallic: f32,
+
+    /// The perceptual roughness of the material in [0..1].
+    pub roughness: f32,
+
+    /// The emitted color of the material.
+    pub emissive: Color3D,
+
+    /// The texture multiplied with `base_color`.
+    pub base_color_texture: Option<TextureSlot>,
+
+    /// The texture holding the roughness in its green and the metalness in
+    /// its blue channel. Only set for materials with explicit PBR values.
+    pub metallic_roughness_texture: Option<TextureSlot>,
+
+    /// The tangent space normal map.
+    pub normal_texture: Option<TextureSlot>,
+
+    /// The ambient occlusion map.
+    pub occlusion_texture: Option<TextureSlot>,
+
+    /// The texture multiplied with `emissive`.
+    pub emissive_texture: Option<TextureSlot>,
+
+    /// Whether the values were read from explicit PBR keys, rather than
+    /// estimated from the Phong parameters.
+    pub is_explicit: bool,
+}
+
+impl PbrMaterial {
+    /// Convert `material` to the metallic-roughness model.
+    ///
+    /// If the material has a base color, metalness or roughness key these
+    /// are used directly. A missing base color defaults to white and a
+    /// missing roughness to 1. A missing metalness defaults to 1 for
+    /// materials with glTF keys, following the glTF specification, and to 0
+    /// for materials using only the keys of newer versions of assimp, which
+    /// other formats set as well.
+    ///
+    /// Otherwise the parameters are estimated:
+    ///
+    /// * The base color is the diffuse color (white if missing), with the
+    ///   opacity as its alpha.
+    /// * The roughness is `sqrt(2 / (shininess + 2))`, the usual mapping of
+    ///   a Blinn-Phong exponent to a GGX roughness. Materials without
+    ///   specular highlights (no shininess, or a black specular color) are
+    ///   fully rough.
+    /// * Materials are dielectric, unless their specular color is brighter
+    ///   than 0.5 while their diffuse color is darker than 0.1. Such
+    ///   materials are treated as metals, using the specular color as base
+    ///   color.
+    ///
+    /// The emissive color and the textures are used as they are in both
+    /// cases.
+    pub fn from_material(material: &Material) -> PbrMaterial {
+        let texture = |tex_type| material.get_texture(tex_type, 0);
+        let mut pbr = PbrMaterial {
+            base_color: Color4D { r: 1.0, g: 1.0, b: 1.0, a: 1.0 },
+            metallic: 0.0,
+            roughness: 1.0,
+            emissive: material.get_color(AI_MATKEY_COLOR_EMISSIVE)
+                              .map(rgb)
+                              .unwrap_or(Color3D { r: 0.0, g: 0.0, b: 0.0 }),
+            base_color_texture: texture(TextureType::Diffuse),
+            metallic_roughness_texture: None,
+            normal_texture: texture(TextureType::Normals),
+            occlusion_texture: texture(TextureType::Lightmap),
+            emissive_texture: texture(TextureType::Emissive),
+            is_explicit: false,
+        };
+
+        let gltf_base_color =
+            material.get_color(AI_MATKEY_GLTF_BASE_COLOR_FACTOR);
+        let gltf_metallic = material.get_float(AI_MATKEY_GLTF_METALLIC_FACTOR);
+        let gltf_roughness =
+            material.get_float(AI_MATKEY_GLTF_ROUGHNESS_FACTOR);
+        let is_gltf = gltf_base_color.is_some() || gltf_metallic.is_some() ||
+                      gltf_roughness.is_some();
+
+        let base_color = gltf_base_color
+            .or_else(|| material.get_color(AI_MATKEY_BASE_COLOR));
+        let metallic = gltf_metallic
+            .or_else(|| material.get_float(AI_MATKEY_METALLIC_FACTOR));
+        let roughness = gltf_roughness
+            .or_else(|| material.get_float(AI_MATKEY_ROUGHNESS_FACTOR));
+
+        if base_color.is_some() || metallic.is_some() || roughness.is_some() {
+            let default_metallic = if is_gltf { 1.0 } else { 0.0 };
+            pbr.base_color = base_color.unwrap_or(pbr.base_color);
+            pbr.metallic = clamp(metallic.unwrap_or(default_metallic));
+            pbr.roughness = clamp(roughness.unwrap_or(1.0));
+            pbr.metallic_roughness_texture = texture(TextureType::Unknown);
+            pbr.is_explicit = true;
+            return pbr;
+        }
+
+        let diffuse = material.get_color(AI_MATKEY_COLOR_DIFFUSE)
+                              .unwrap_or(pbr.base_color);
+        let specular = material.get_color(AI_MATKEY_COLOR_SPECULAR)
+                               .map(rgb)
+                               .unwrap_or(Color3D { r: 0.0, g: 0.0, b: 0.0 });
+        let strength = material.get_shininess_strength().unwrap_or(1.0);
+        let opacity = material.get_opacity().unwrap_or(diffuse.a);
+
+        let specular_level = max_component(specular) * strength;
+        pbr.roughness = match material.get_shininess() {
+            Some(shininess) if shininess > 0.0 && specular_level > 0.0 =>
+                clamp((2.0 / (shininess + 2.0)).sqrt()),
+            _ => 1.0,
+        };
+
+        if specular_level > 0.5 && max_component(rgb(diffuse)) < 0.1 {
+            pbr.metallic = 1.0;
+            pbr.base_color = Color4D { r: specular.r, g: specular.g,
+                                       b: specular.b, a: opacity };
+        } else {
+            pbr.base_color = Color4D { r: diffuse.r, g: diffuse.g,
+                                       b: diffuse.b, a: opacity };
+        }
+        pbr
+    }
+}
+
+fn rgb(color: Color4D) -> Color3D {
+    Color3D { r: color.r, g: color.g, b: color.b }
+}
+
+fn max_component(color: Color3D) -> f32 {
+    color.r.max(color.g).max(color.b)
+}
+
+fn clamp(value: f32) -> f32 {
+    value.max(0.0).min(1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::PbrMaterial;
+    use fixtures::{import_gltf, import_obj, find_material, OBJ};
+
+    #[test]
+    fn test_from_phong() {
+        let scene = import_obj(OBJ, "newmtl red
+Kd 1 0 0
+Ks 0.2 0.2 0.2
+Ns 98
+d 0.5
+illum 2
+");
+        let material = find_material(&scene, "red");
+        let pbr = PbrMaterial::from_material(material);
+
+        assert!(!pbr.is_explicit);
+        let base = pbr.base_color;
+        assert_eq!((base.r, base.g, base.b, base.a), (1.0, 0.0, 0.0, 0.5));
+        assert_eq!(pbr.metallic, 0.0);
+        assert!((pbr.roughness - 0.14).abs() < 0.001);
+        assert!(pbr.base_color_texture.is_none());
+    }
+
+    #[test]
+    fn test_from_phong_metal() {
+        let scene = import_obj(OBJ, "newmtl red
+Kd 0.05 0.05 0.05
+Ks 0.9 0.8 0.7
+Ns 200
+illum 2
+");
+        let material = find_material(&scene, "red");
+        let pbr = PbrMaterial::from_material(material);
+
+        // A dark diffuse color with bright highlights is taken as a metal
+        assert!(!pbr.is_explicit);
+        assert_eq!(pbr.metallic, 1.0);
+        let base = pbr.base_color;
+        assert_eq!((base.r, base.g, base.b, base.a), (0.9, 0.8, 0.7, 1.0));
+        assert!((pbr.roughness - 0.0995).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_explicit() {
+        let scene = import_gltf(r#"[ { "name": "metal",
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [ 0.5, 0.25, 1.0, 0.75 ],
+                "metallicFactor": 0.25,
+                "roughnessFactor": 0.5,
+                "metallicRoughnessTexture": { "index": 0 } } } ]"#);
+        let material = find_material(&scene, "metal");
+        let pbr = PbrMaterial::from_material(material);
+
+        assert!(pbr.is_explicit);
+        let base = pbr.base_color;
+        assert_eq!((base.r, base.g, base.b, base.a), (0.5, 0.25, 1.0, 0.75));
+        assert_eq!(pbr.metallic, 0.25);
+        assert_eq!(pbr.roughness, 0.5);
+        let texture = pbr.metallic_roughness_texture.unwrap();
+        assert_eq!(texture.path, "mr.png");
+        assert!(pbr.base_color_texture.is_none());
+    }
+
+    #[test]
+    fn test_explicit_defaults() {
+        // Assimp may leave out factors equal to their glTF defaults
+        let scene = import_gltf(r#"[ { "name": "plain",
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [ 0.5, 0.5, 0.5, 1.0 ] } } ]"#);
+        let material = find_material(&scene, "plain");
+        let pbr = PbrMaterial::from_material(material);
+
+        assert!(pbr.is_explicit);
+        let base = pbr.base_color;
+        assert_eq!((base.r, base.g, base.b, base.a), (0.5, 0.5, 0.5, 1.0));
+        assert_eq!(pbr.metallic, 1.0);
+        assert_eq!(pbr.roughness, 1.0);
+        assert!(pbr.metallic_roughness_texture.is_none());
+        let emissive = pbr.emissive;
+        assert_eq!((emissive.r, emissive.g, emissive.b), (0.0, 0.0, 0.0));
+    }
+}
+
+// vim: et tw=78 sw=4:
@@ -1,18 +1,26 @@
 //! Defines the data structures in which the imported scene is returned.
 
-use libc::{c_uint, c_void};
+use libc::{c_char, c_uint, c_void};
 use std::mem;
 use std::fmt;
+use std::fs;
+use std::collections::VecDeque;
+use std::io;
+use std::path::Path;
+use std::ptr::{self, NonNull};
 
 use animation::Animation;
 use camera::Camera;
 use light::Light;
-use material::Material;
+use material::{Material, TextureType};
 use mesh::Mesh;
-use texture::Texture;
-use types::{Matrix4x4, AiString, MemoryInfo};
+use texture::{self, Texture, TextureSource};
+use texture::{ExtractedTextures, ExtractedTextureRef};
+use types::{Matrix4x4, AiString, MemoryInfo, Aabb};
 use util::{ptr_ptr_to_slice, ptr_to_slice};
 use postprocess::Process;
+use importer::ImportError;
+use info;
 use ffi;
 
 /// A node in the imported hierarchy.
@@ -78,6 +86,128 @@ impl Node {
     pub fn get_meshes(&self) -> &[u32] {
         unsafe { ptr_to_slice(self.meshes, self.num_meshes as usize) }
     }
+
+    /// Get the transformation of this node relative to the root node, i.e.
+    /// the product of the transformations of all its ancestors and itself.
+    pub fn get_world_transform(&self) -> Matrix4x4 {
+        match self.get_parent() {
+            Some(parent) => parent.get_world_transform() * self.transformation,
+            None => self.transformation,
+        }
+    }
+
+    /// Get the nodes from the root node down to this node, both included.
+    pub fn get_path(&self) -> Vec<&Node> {
+        let mut path = vec![self];
+        let mut node = self;
+        while let Some(parent) = node.get_parent() {
+            path.push(parent);
+            node = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Iterate over this node and all its descendants, depth first.
+    ///
+    /// Parents are visited before their children, and children in order.
+    /// World transforms are relative to the parent of this node.
+    pub fn iter_depth_first(&self) -> DepthFirstNodes<'_> {
+        DepthFirstNodes {
+            stack: vec![NodeVisit::root(self)],
+        }
+    }
+
+    /// Iterate over this node and all its descendants, breadth first.
+    ///
+    /// All nodes at one depth are visited before any node at the next.
+    /// World transforms are relative to the parent of this node.
+    pub fn iter_breadth_first(&self) -> BreadthFirstNodes<'_> {
+        let mut queue = VecDeque::new();
+        queue.push_back(NodeVisit::root(self));
+        BreadthFirstNodes {
+            queue: queue,
+        }
+    }
+
+    /// Find the first node named `name` among this node and its
+    /// descendants, searching depth first.
+    pub fn find_node(&self, name: &str) -> Option<&Node> {
+        self.iter_depth_first()
+            .map(|visit| visit.node)
+            .find(|node| node.name.as_str().ok() == Some(name))
+    }
+}
+
+/// A node visited by one of the node iterators, such as
+/// `Scene::iter_nodes_depth_first`.
+#[derive(Copy, Clone)]
+pub struct NodeVisit<'a> {
+    /// The node
+    pub node: &'a Node,
+
+    /// The accumulated transformation of the node and all its ancestors
+    pub world_transform: Matrix4x4,
+
+    /// The depth of the node, 0 for the node the iteration started at
+    pub depth: usize,
+}
+
+impl<'a> NodeVisit<'a> {
+    fn root(node: &'a Node) -> NodeVisit<'a> {
+        NodeVisit {
+            node: node,
+            world_transform: node.transformation,
+            depth: 0,
+        }
+    }
+
+    fn children(&self) -> impl DoubleEndedIterator<Item=NodeVisit<'a>> {
+        let world_transform = self.world_transform;
+        let depth = self.depth + 1;
+        self.node.get_children().iter().map(move |child| {
+            NodeVisit {
+                node: *child,
+                world_transform: world_transform * child.transformation,
+                depth: depth,
+            }
+        })
+    }
+}
+
+/// Iterates over a node hierarchy depth first.
+///
+/// Created by `Node::iter_depth_first` or `Scene::iter_nodes_depth_first`.
+pub struct DepthFirstNodes<'a> {
+    stack: Vec<NodeVisit<'a>>,
+}
+
+impl<'a> Iterator for DepthFirstNodes<'a> {
+    type Item = NodeVisit<'a>;
+
+    fn next(&mut self) -> Option<NodeVisit<'a>> {
+        let visit = self.stack.pop()?;
+        self.stack.extend(visit.children().rev());
+        Some(visit)
+    }
+}
+
+/// Iterates over a node hierarchy breadth first.
+///
+/// Created by `Node::iter_breadth_first` or
+/// `Scene::iter_nodes_breadth_first`.
+pub struct BreadthFirstNodes<'a> {
+    queue: VecDeque<NodeVisit<'a>>,
+}
+
+impl<'a> Iterator for BreadthFirstNodes<'a> {
+    type Item = NodeVisit<'a>;
+
+    fn next(&mut self) -> Option<NodeVisit<'a>> {
+        let visit = self.queue.pop_front()?;
+        self.queue.extend(visit.children());
+        Some(visit)
+    }
 }
 
 
@@ -221,13 +351,29 @@ pub struct RawScene {
     pub private: *mut c_void,
 }
 
+/// The texture types a material can reference.
+const TEXTURE_TYPES: [TextureType; 12] = [
+    TextureType::Diffuse, TextureType::Specular, TextureType::Ambient,
+    TextureType::Emissive, TextureType::Height, TextureType::Normals,
+    TextureType::Shininess, TextureType::Opacity, TextureType::Displacement,
+    TextureType::Lightmap, TextureType::Reflection, TextureType::Unknown,
+];
+
 /// The root structure of the imported data.
 ///
 /// Everything that was imported from the given file can be accessed from here.
-pub struct Scene<'a> {
+///
+/// The scene owns the data assimp allocated for it, everything borrowed from
+/// it lives as long as the `Scene`. Scenes can be sent to, and shared
+/// between, threads: the data is never modified through a shared reference.
+pub struct Scene {
     /// Note we use this struct to wrap the RawScene so that we
     /// can call `aiReleaseImport` when it gets dropped.
-    raw_scene: &'a RawScene,
+    raw_scene: NonNull<RawScene>,
+
+    /// Whether the scene is still attached to the assimp importer which
+    /// created it. Copies made by `clone` are not.
+    owned_by_importer: bool,
 
     /// Any combination of the flags in `SceneFlags`.
     ///
@@ -256,12 +402,23 @@ pub struct Scene<'a> {
     pub num_cameras: c_uint,
 }
 
-impl<'a> Scene<'a> {
+// Assimp doesn't hold on to a scene after importing it, the data is only
+// ever modified through `&mut Scene`.
+unsafe impl Send for Scene {}
+unsafe impl Sync for Scene {}
+
+impl Scene {
+    /// Take ownership of a scene returned by assimp.
+    ///
+    /// `raw` must be non-null and must not be released by anyone else, the
+    /// `Scene` releases it when dropped.
     #[doc(hidden)]
-    pub unsafe fn from_raw_scene(raw: *const RawScene) -> Scene<'a> {
-        let raw = &*raw;
+    pub unsafe fn from_raw_scene(raw: *const RawScene) -> Scene {
+        let raw_scene = NonNull::new_unchecked(raw as *mut RawScene);
+        let raw = raw_scene.as_ref();
         Scene {
-            raw_scene: raw,
+            raw_scene: raw_scene,
+            owned_by_importer: true,
             flags: raw.flags,
             num_meshes: raw.num_meshes,
             num_materials: raw.num_materials,
@@ -272,6 +429,15 @@ impl<'a> Scene<'a> {
         }
     }
 
+    #[doc(hidden)]
+    pub fn as_raw_scene(&self) -> *const RawScene {
+        self.raw_scene.as_ptr()
+    }
+
+    fn raw(&self) -> &RawScene {
+        unsafe { self.raw_scene.as_ref() }
+    }
+
     /// Check if the given scene flag is set
     ///
     /// See `SceneFlags`
@@ -286,16 +452,87 @@ impl<'a> Scene<'a> {
     /// the format and content of the imported file.
     pub fn get_root_node(&self) -> &Node {
         unsafe {
-            &*(self.raw_scene.root_node)
+            &*(self.raw().root_node)
+        }
+    }
+
+    /// Iterate over all nodes of the scene depth first, starting at the
+    /// root node.
+    ///
+    /// Each node is returned with its world transform and its depth.
+    pub fn iter_nodes_depth_first(&self) -> DepthFirstNodes<'_> {
+        self.get_root_node().iter_depth_first()
+    }
+
+    /// Iterate over all nodes of the scene breadth first, starting at the
+    /// root node.
+    ///
+    /// Each node is returned with its world transform and its depth.
+    pub fn iter_nodes_breadth_first(&self) -> BreadthFirstNodes<'_> {
+        self.get_root_node().iter_breadth_first()
+    }
+
+    /// Find the first node named `name`, searching depth first.
+    pub fn find_node(&self, name: &str) -> Option<&Node> {
+        self.get_root_node().find_node(name)
+    }
+
+    /// Get the names of the nodes from the root node down to `node`, joined
+    /// with `/`.
+    ///
+    /// Unnamed nodes are written as `<index>`, their index among the
+    /// children of their parent.
+    pub fn node_path(&self, node: &Node) -> String {
+        let path = node.get_path();
+        let mut names = Vec::with_capacity(path.len());
+        for (i, node) in path.iter().enumerate() {
+            let name = node.name.as_str().unwrap_or("");
+            if !name.is_empty() || i == 0 {
+                names.push(name.to_string());
+                continue;
+            }
+            let node: *const Node = *node;
+            let index = path[i - 1].get_children().iter()
+                .position(|sibling| *sibling as *const Node == node)
+                .unwrap_or(0);
+            names.push(format!("<{}>", index));
         }
+        names.join("/")
+    }
+
+    /// Get the bounding box of all meshes in the scene, in world space.
+    ///
+    /// World space is the space the root node is placed in, so the
+    /// transformation of the root node itself is applied as well.
+    ///
+    /// Every node referencing a mesh adds the mesh's vertices transformed by
+    /// the node's world transform, so meshes instanced by several nodes are
+    /// included once per node. Meshes not referenced by any node are left
+    /// out.
+    pub fn aabb(&self) -> Aabb {
+        let meshes = self.get_meshes();
+        let mut aabb = Aabb::empty();
+        for visit in self.iter_nodes_depth_first() {
+            for &index in visit.node.get_meshes() {
+                let mesh = match meshes.get(index as usize) {
+                    Some(mesh) => mesh,
+                    None => continue,
+                };
+                for vertex in mesh.get_vertices() {
+                    aabb.add_point(&visit.world_transform
+                                         .transform_point(vertex));
+                }
+            }
+        }
+        aabb
     }
 
     /// Get the array of animations.
     ///
     /// All animations imported from the given file are listed here.
     pub fn get_animations(&self) -> &[&Animation] {
-        unsafe { ptr_ptr_to_slice(self.raw_scene.animations,
-                                  self.raw_scene.num_animations as usize) }
+        unsafe { ptr_ptr_to_slice(self.raw().animations,
+                                  self.raw().num_animations as usize) }
     }
 
     /// Get the array of meshes.
@@ -304,16 +541,16 @@ impl<'a> Scene<'a> {
     /// this array. If the `SceneFlags::Incomplete` flag is not set there
     /// will always be at least one mesh.
     pub fn get_meshes(&self) -> &[&Mesh] {
-        unsafe { ptr_ptr_to_slice(self.raw_scene.meshes,
-                                  self.raw_scene.num_meshes as usize) }
+        unsafe { ptr_ptr_to_slice(self.raw().meshes,
+                                  self.raw().num_meshes as usize) }
     }
 
     /// Get the array of light sources.
     ///
     /// All light sources imported from the given file are listed here.
     pub fn get_lights(&self) -> &[&Light] {
-        unsafe { ptr_ptr_to_slice(self.raw_scene.lights,
-                                  self.raw_scene.num_lights as usize) }
+        unsafe { ptr_ptr_to_slice(self.raw().lights,
+                                  self.raw().num_lights as usize) }
     }
 
     /// Get the array of cameras.
@@ -322,8 +559,8 @@ impl<'a> Scene<'a> {
     /// The first camera in the array (if existing) is the default camera view
     /// into the scene.
     pub fn get_cameras(&self) -> &[&Camera] {
-        unsafe { ptr_ptr_to_slice(self.raw_scene.cameras,
-                                  self.raw_scene.num_cameras as usize) }
+        unsafe { ptr_ptr_to_slice(self.raw().cameras,
+                                  self.raw().num_cameras as usize) }
     }
 
     /// Get the array of materials.
@@ -332,8 +569,8 @@ impl<'a> Scene<'a> {
     /// array. If the `SceneFlags::Incomplete` flag is not set there will
     /// always be at least ONE material.
     pub fn get_materials(&self) -> &[&Material] {
-        unsafe { ptr_ptr_to_slice(self.raw_scene.materials,
-                                  self.raw_scene.num_materials as usize) }
+        unsafe { ptr_ptr_to_slice(self.raw().materials,
+                                  self.raw().num_materials as usize) }
     }
 
     /// Get the array of embedded textures.
@@ -342,15 +579,102 @@ impl<'a> Scene<'a> {
     /// An example is Quake's MDL format (which is also used by
     /// some GameStudio versions)
     pub fn get_textures(&self) -> &[&Texture] {
-        unsafe { ptr_ptr_to_slice(self.raw_scene.textures,
-                                  self.raw_scene.num_textures as usize) }
+        unsafe { ptr_ptr_to_slice(self.raw().textures,
+                                  self.raw().num_textures as usize) }
+    }
+
+    /// Find the image referenced by the texture path `reference`, e.g. the
+    /// `path` of a `TextureSlot`.
+    ///
+    /// `model_path` is the path the scene was imported from. References of
+    /// the form `"*<index>"` return the embedded texture, all others are
+    /// looked up on disk as described on `texture::find_texture_file`.
+    ///
+    /// Returns `None` if the texture can't be found.
+    pub fn resolve_texture<P: AsRef<Path>>(&self,
+                                           model_path: P,
+                                           reference: &str)
+                                           -> Option<TextureSource<'_>> {
+        match texture::embedded_texture_index(reference) {
+            Some(index) => self.get_textures().get(index).map(|tex| {
+                TextureSource::Embedded(index, *tex)
+            }),
+            None => texture::find_texture_file(model_path, reference)
+                        .map(TextureSource::File),
+        }
+    }
+
+    /// Write all embedded textures to files in the directory `dir`.
+    ///
+    /// The directory is created if necessary. Textures are named
+    /// `texture_<index>.<ext>`, with the extension given by
+    /// `Texture::get_file_extension`. Writing uncompressed textures requires
+    /// the `image` feature.
+    ///
+    /// The returned `ExtractedTextures` lists the written files, and the
+    /// new path of every material texture referencing one of them. A texture
+    /// which can't be written is reported in `ExtractedTextures::failures`,
+    /// the others are still extracted. Only failing to create the directory
+    /// is returned as an error.
+    pub fn extract_textures<P: AsRef<Path>>(&self, dir: P)
+                                            -> io::Result<ExtractedTextures> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let mut files = Vec::with_capacity(self.get_textures().len());
+        let mut failures = Vec::new();
+        for (i, tex) in self.get_textures().iter().enumerate() {
+            let path = dir.join(format!("texture_{}.{}",
+                                        i, tex.get_file_extension()));
+            match tex.save(&path) {
+                Ok(()) => files.push(Some(path)),
+                Err(e) => {
+                    files.push(None);
+                    failures.push((i, e));
+                }
+            }
+        }
+
+        let mut references = Vec::new();
+        for (i, material) in self.get_materials().iter().enumerate() {
+            for &tex_type in TEXTURE_TYPES.iter() {
+                for index in 0..material.get_texture_count(tex_type) {
+                    let slot = match material.get_texture(tex_type, index) {
+                        Some(slot) => slot,
+                        None => continue,
+                    };
+                    let embedded = match texture::embedded_texture_index(
+                        &slot.path) {
+                        Some(e) => e,
+                        None => continue,
+                    };
+                    let path = match files.get(embedded) {
+                        Some(&Some(ref path)) => path.clone(),
+                        _ => continue,
+                    };
+                    references.push(ExtractedTextureRef {
+                        material: i,
+                        tex_type: tex_type,
+                        index: index,
+                        embedded: embedded,
+                        path: path,
+                    });
+                }
+            }
+        }
+
+        Ok(ExtractedTextures {
+            files: files,
+            references: references,
+            failures: failures,
+        })
     }
 
     /// Get the amount of memory used to store this scene.
     pub fn get_memory_info(&self) -> MemoryInfo {
         unsafe {
             let mut mem_info = mem::zeroed();
-            ffi::aiGetMemoryRequirements(self.raw_scene, &mut mem_info);
+            ffi::aiGetMemoryRequirements(self.as_raw_scene(), &mut mem_info);
             mem_info
         }
     }
@@ -365,26 +689,86 @@ impl<'a> Scene<'a> {
     ///
     /// * `steps` A list of post processing steps to perform on the `Scene`.
     ///
-    /// This process can fail if using `Process::ValidateDS` in which case an
-    /// error is returned and further usage of the scene is invalid.
-    pub fn apply_postprocessing(&mut self,
+    /// The scene is consumed and returned again once the steps have run.
+    /// This process can fail if using `Process::ValidateDS`, in which case
+    /// assimp releases the scene and only the error is returned.
+    ///
+    /// Assimp can only post process the scenes created by its importer. A
+    /// scene created by `clone` is exported to the `assbin` format and
+    /// imported again instead, running the steps during that import. This
+    /// requires both the `assbin` exporter and importer to be compiled into
+    /// assimp. The import properties set on the `Importer` which created the
+    /// original scene are not used for it.
+    ///
+    /// The error carries the message assimp reported for the failure.
+    pub fn apply_postprocessing(mut self,
                                 steps: &[Process])
-                                -> Result<(), &str> {
+                                -> Result<Scene, ImportError> {
+        let flags = steps.iter().fold(0, |x, &y| x | y as u32);
+        if !self.owned_by_importer {
+            return self.reimport(flags);
+        }
+
         unsafe {
-            let flags = steps.iter().fold(0, |x, &y| x | y as u32);
-            let scene = ffi::aiApplyPostProcessing(self.raw_scene,
+            let scene = ffi::aiApplyPostProcessing(self.as_raw_scene(),
                                                    flags);
             if scene.is_null() {
-                //TODO: invalidate the scene
-                Err("Post processing failed")
-            } else {
-                Ok(())
+                // Assimp has released the scene already
+                mem::forget(self);
+                return Err(ImportError::from_last_error());
+            }
+        }
+        // Steps like `PreTransformVertices` change the number of meshes,
+        // materials and so on
+        self.update_counts();
+        Ok(self)
+    }
+
+    /// Copy the counts of the raw scene into the public fields.
+    fn update_counts(&mut self) {
+        let raw = unsafe { self.raw_scene.as_ref() };
+        self.flags = raw.flags;
+        self.num_meshes = raw.num_meshes;
+        self.num_materials = raw.num_materials;
+        self.num_animations = raw.num_animations;
+        self.num_textures = raw.num_textures;
+        self.num_lights = raw.num_lights;
+        self.num_cameras = raw.num_cameras;
+    }
+
+    /// Import a fresh copy of this scene, running the post processing steps
+    /// in `flags` on the way.
+    fn reimport(self, flags: c_uint) -> Result<Scene, ImportError> {
+        if !info::is_export_format_supported("assbin") {
+            return Err(ImportError::UnsupportedFormat(
+                "post processing a copied scene needs the assbin exporter, \
+                 which is not compiled into assimp".to_string()));
+        }
+        let format = b"assbin\0".as_ptr() as *const c_char;
+        unsafe {
+            let blob = ffi::aiExportSceneToBlob(self.as_raw_scene(),
+                                                format, 0);
+            if blob.is_null() {
+                return Err(ImportError::ParseFailed(
+                    "failed to export the copied scene as assbin"
+                        .to_string()));
             }
+            let raw = ffi::aiImportFileFromMemory((*blob).data as *const c_char,
+                                                  (*blob).size as c_uint,
+                                                  flags,
+                                                  format);
+            ffi::aiReleaseExportBlob(blob);
+            if raw.is_null() {
+                return Err(ImportError::from_last_error());
+            }
+            // The new scene reads its counts from the imported data, dropping
+            // `self` releases the copy
+            Ok(Scene::from_raw_scene(raw))
         }
     }
 }
 
-impl<'a> fmt::Display for Scene<'a> {
+impl fmt::Display for Scene {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Scene {{ \
         num_meshes: {}, \
@@ -403,25 +787,261 @@ impl<'a> fmt::Display for Scene<'a> {
 }
 
 // #[unsafe_destructor]
-impl<'a> Drop for Scene<'a> {
+impl Drop for Scene {
     fn drop(&mut self) {
         // NOTE: it's okay to call this on a scene returned by aiCopyScene
         // based on comments in the assimp source code
-        unsafe { ffi::aiReleaseImport(mem::transmute(self.raw_scene)) }
+        unsafe { ffi::aiReleaseImport(self.as_raw_scene()) }
+    }
+}
+
+
+/// Cloning a scene creates a deep copy of all its data using
+/// `aiCopyScene`.
+///
+/// The copy is independent of the original: both can be modified, post
+/// processed and dropped separately.
+impl Clone for Scene {
+    fn clone(&self) -> Scene {
+        unsafe {
+            let mut raw_copy: *mut RawScene = ptr::null_mut();
+            ffi::aiCopyScene(self.as_raw_scene(), &mut raw_copy);
+            if raw_copy.is_null() {
+                panic!("failed to clone scene")
+            }
+            let mut scene = Scene::from_raw_scene(raw_copy);
+            scene.owned_by_importer = false;
+            scene
+        }
     }
 }
 
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use std::thread;
+
+    use importer::Importer;
+    use material::TextureType;
+    use postprocess::Process;
+    use texture::TextureSource;
+
+    #[test]
+    fn test_clone() {
+        let importer = Importer::new();
+        let scene = importer.import_from_file("examples/assets/cube.dae")
+                            .unwrap();
 
-// impl<'a> Clone for Scene<'a> {
-//     fn clone(&self) -> Scene<'a> {
-//         unsafe {
-//             let raw_copy : *mut*mut RawScene= ptr::null_mut();
-//             ffi::aiCopyScene(self.raw_scene, raw_copy);
-//             if raw_copy.is_null() {
-//                 panic!("failed to clone scene")
-//             } else {
-//                 Scene::from_raw_scene(*raw_copy)
-//             }
-//         }
-//     }
-// }
+        let render = scene.clone();
+        let physics = scene.clone();
+        drop(scene);
+
+        let render = render.apply_postprocessing(
+            &[Process::PreTransformVertices]).unwrap();
+        let physics = physics.apply_postprocessing(
+            &[Process::JoinIdenticalVertices]).unwrap();
+        assert!(render.num_meshes > 0);
+        assert_eq!(render.get_meshes().len(), render.num_meshes as usize);
+        assert_eq!(physics.num_meshes, physics.get_meshes().len() as u32);
+    }
+
+    #[test]
+    fn test_apply_postprocessing() {
+        let importer = Importer::new();
+        let scene = importer.import_from_file("examples/assets/cube.dae")
+                            .unwrap();
+        let scene = scene.apply_postprocessing(
+            &[Process::PreTransformVertices]).unwrap();
+        assert_eq!(scene.num_meshes, scene.get_meshes().len() as u32);
+        assert_eq!(scene.num_materials, scene.get_materials().len() as u32);
+    }
+
+    #[test]
+    fn test_node_iterators() {
+        let importer = Importer::new();
+        let scene = importer.import_from_file("examples/assets/cube.dae")
+                            .unwrap();
+        let root = scene.get_root_node();
+
+        let depth_first: Vec<_> = scene.iter_nodes_depth_first().collect();
+        let breadth_first: Vec<_> = scene.iter_nodes_breadth_first().collect();
+        assert_eq!(depth_first.len(), breadth_first.len());
+        assert!(depth_first.len() > 1);
+        assert_eq!(depth_first[0].depth, 0);
+        assert_eq!(depth_first[1].depth, 1);
+
+        for visit in depth_first.iter() {
+            assert_eq!(visit.world_transform,
+                       visit.node.get_world_transform());
+            assert_eq!(visit.node.get_path().len(), visit.depth + 1);
+        }
+
+        let cube = scene.find_node("Cube").unwrap();
+        let root_name = root.name.as_str().unwrap();
+        assert_eq!(scene.node_path(cube), format!("{}/Cube", root_name));
+        assert!(scene.find_node("no such node").is_none());
+    }
+
+    #[test]
+    fn test_leaf_nodes() {
+        let importer = Importer::new();
+        let scene = importer.import_from_file("examples/assets/cube.dae")
+                            .unwrap();
+
+        // Assimp leaves the child and mesh arrays of these nodes null
+        let leaves: Vec<_> = scene.iter_nodes_breadth_first()
+            .filter(|visit| visit.node.num_children == 0)
+            .collect();
+        assert!(!leaves.is_empty());
+        for visit in leaves {
+            assert!(visit.node.get_children().is_empty());
+            assert_eq!(visit.node.get_meshes().len(),
+                       visit.node.num_meshes as usize);
+        }
+        let empty = scene.iter_nodes_depth_first()
+            .find(|visit| visit.node.num_meshes == 0)
+            .unwrap();
+        assert!(empty.node.get_meshes().is_empty());
+        assert!(scene.get_textures().is_empty());
+    }
+
+    #[test]
+    fn test_aabb() {
+        let importer = Importer::new();
+        let scene = importer.import_from_file("examples/assets/cube.dae")
+                            .unwrap();
+        let aabb = scene.aabb();
+        assert!(!aabb.is_empty());
+
+        let cube = scene.find_node("Cube").unwrap();
+        let world = cube.get_world_transform();
+        let mesh = scene.get_meshes()[cube.get_meshes()[0] as usize];
+        assert!(!mesh.aabb().is_empty());
+        for vertex in mesh.get_vertices() {
+            assert!(aabb.contains(&world.transform_point(vertex)));
+        }
+        let sphere = mesh.bounding_sphere();
+        assert!(mesh.aabb().contains(&sphere.center));
+    }
+
+    // A triangle whose material uses a texture embedded as a data URI
+    const EMBEDDED_GLTF: &'static str = concat!(r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [ { "nodes": [ 0 ] } ],
+    "nodes": [ { "mesh": 0 } ],
+    "meshes": [ { "primitives": [
+        { "attributes": { "POSITION": 0 }, "material": 0 } ] } ],
+    "materials": [
+        { "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } } } ],
+    "textures": [ { "source": 0 } ],
+    "images": [ { "uri": "data:image/png;base64,bm90IHJlYWxseSBhIHBuZw==" } ],
+    "buffers": [ { "byteLength": 36, "uri":
+        "data:application/octet-stream;base64,"#,
+        "AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA", r#"" } ],
+    "bufferViews": [ { "buffer": 0, "byteLength": 36 } ],
+    "accessors": [ { "bufferView": 0, "componentType": 5126, "count": 3,
+                     "type": "VEC3", "min": [ 0, 0, 0 ], "max": [ 1, 1, 0 ] } ]
+}"#);
+
+    #[test]
+    fn test_extract_textures() {
+        let importer = Importer::new();
+        let scene = importer
+            .import_from_memory(EMBEDDED_GLTF.as_bytes(), "gltf")
+            .unwrap();
+        assert_eq!(scene.get_textures().len(), 1);
+
+        let dir = env::temp_dir().join(
+            format!("rust-assimp-extract-test-{}", process::id()));
+        let extracted = scene.extract_textures(&dir).unwrap();
+        let file = dir.join("texture_0.png");
+        let data = fs::read(&file);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(extracted.failures.is_empty());
+        assert_eq!(extracted.files, vec![Some(file.clone())]);
+        assert_eq!(data.unwrap(), b"not really a png".to_vec());
+
+        let reference = extracted.references.iter()
+            .find(|r| r.tex_type == TextureType::Diffuse)
+            .expect("no reference to the embedded texture");
+        assert_eq!((reference.material, reference.index), (0, 0));
+        assert_eq!(reference.embedded, 0);
+        assert_eq!(reference.path, file);
+
+        let slot = scene.get_materials()[0]
+            .get_texture(TextureType::Diffuse, 0)
+            .unwrap();
+        assert_eq!(slot.path, "*0");
+    }
+
+    #[test]
+    fn test_resolve_texture() {
+        let importer = Importer::new();
+        let scene = importer
+            .import_from_memory(EMBEDDED_GLTF.as_bytes(), "gltf")
+            .unwrap();
+
+        let dir = env::temp_dir().join(
+            format!("rust-assimp-resolve-test-{}", process::id()));
+        let files = ["tex/wood.png", "Maps/Stone.PNG",
+                     "assets/textures/metal.jpg", "a/b/c/deep.png"];
+        for file in files.iter() {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, b"").unwrap();
+        }
+        let model = dir.join("model.gltf");
+        let wood = dir.join("tex/wood.png");
+        let absolute = wood.to_str().unwrap().to_string();
+
+        let resolve = |reference: &str| {
+            match scene.resolve_texture(&model, reference) {
+                Some(TextureSource::File(path)) => Some(path),
+                Some(TextureSource::Embedded(..)) =>
+                    panic!("{:?} resolved to an embedded texture", reference),
+                None => None,
+            }
+        };
+        let found = [
+            resolve(&absolute),
+            resolve("tex\\wood.png"),
+            resolve("./maps/stone.png"),
+            resolve("C:\\work\\METAL.jpg"),
+            resolve("deep.png"),
+            resolve("missing.png"),
+        ];
+        let embedded = match scene.resolve_texture(&model, "*0") {
+            Some(TextureSource::Embedded(index, texture)) =>
+                Some((index, texture.get_format_hint() == Some("png"))),
+            _ => None,
+        };
+        let out_of_range = scene.resolve_texture(&model, "*1").is_none();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Absolute, then relative to the model, then case insensitive
+        assert_eq!(found[0], Some(wood.clone()));
+        assert_eq!(found[1], Some(wood));
+        assert_eq!(found[2], Some(dir.join("Maps").join("Stone.PNG")));
+        // Then by file name, at most two directories deep
+        assert_eq!(found[3], Some(dir.join("assets/textures/metal.jpg")));
+        assert_eq!(found[4], None);
+        assert_eq!(found[5], None);
+
+        assert_eq!(embedded, Some((0, true)));
+        assert!(out_of_range);
+    }
+
+    #[test]
+    fn test_send_to_thread() {
+        let scene = thread::spawn(|| {
+            let importer = Importer::new();
+            importer.import_from_file("examples/assets/cube.dae").unwrap()
+        }).join().unwrap();
+        assert!(scene.get_root_node().num_meshes > 0 ||
+                scene.get_root_node().num_children > 0);
+    }
+}
@@ -0,0 +1,452 @@
+//! Packing of bone weights into per-vertex influences for GPU skinning
+//!
+//! Assimp stores the influences of each bone as a list of weighted vertices,
+//! see `Bone::get_weights`. Vertex shaders need the inverse: a fixed number
+//! of joint indices and weights per vertex. `SkinBuilder` converts the bones
+//! of a mesh into that layout, keeping the strongest influences of every
+//! vertex.
+//!
+//! The skeleton itself is stored implicitly, as the nodes named after the
+//! bones. `Skeleton` collects these nodes into a flat joint array.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+use mesh::Mesh;
+use scene::{Node, Scene};
+use types::Matrix4x4;
+
+/// A bone in the joint table of a `Skin`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SkinBone {
+    /// The name of the bone, which is also the name of the node it is
+    /// attached to
+    pub name: String,
+
+    /// The matrix transforming from mesh space to bone space in bind pose,
+    /// also known as inverse bind matrix
+    pub offset_matrix: Matrix4x4,
+}
+
+/// The bone influences of every vertex of a mesh.
+///
+/// Joint indices refer to `bones`, which lists the bones in the same order
+/// as `Mesh::get_bones`. Every vertex has `max_influences` joints and
+/// weights, sorted by decreasing weight. Unused slots have joint 0 and
+/// weight 0.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Skin {
+    /// The bones referenced by the joint indices
+    pub bones: Vec<SkinBone>,
+
+    /// The number of influences stored per vertex
+    pub max_influences: usize,
+
+    /// The joint indices, `max_influences` per vertex
+    pub joints: Vec<u16>,
+
+    /// The joint weights, `max_influences` per vertex
+    pub weights: Vec<f32>,
+
+    /// The number of vertices which had more influences than
+    /// `max_influences`, and lost their weakest ones
+    pub truncated_vertices: usize,
+
+    /// The number of vertices not influenced by any bone
+    pub unweighted_vertices: usize,
+}
+
+impl Skin {
+    /// Get the number of vertices
+    pub fn get_vertex_count(&self) -> usize {
+        match self.max_influences {
+            0 => 0,
+            n => self.joints.len() / n,
+        }
+    }
+
+    /// Get the joint indices of the vertex with the given index
+    pub fn get_joints(&self, vertex: usize) -> &[u16] {
+        let n = self.max_influences;
+        &self.joints[vertex * n..(vertex + 1) * n]
+    }
+
+    /// Get the joint weights of the vertex with the given index
+    pub fn get_weights(&self, vertex: usize) -> &[f32] {
+        let n = self.max_influences;
+        &self.weights[vertex * n..(vertex + 1) * n]
+    }
+
+    /// Get four joint indices per vertex, the usual layout of a skinning
+    /// vertex attribute.
+    ///
+    /// Vertices with fewer influences are padded with joint 0, additional
+    /// influences are dropped.
+    pub fn to_joints4(&self) -> Vec<[u16; 4]> {
+        let mut joints = Vec::with_capacity(self.get_vertex_count());
+        for i in 0..self.get_vertex_count() {
+            let mut packed = [0; 4];
+            for (dst, src) in packed.iter_mut().zip(self.get_joints(i)) {
+                *dst = *src;
+            }
+            joints.push(packed);
+        }
+        joints
+    }
+
+    /// Get four joint weights per vertex, matching `to_joints4`.
+    ///
+    /// Vertices with fewer influences are padded with weight 0, additional
+    /// influences are dropped without renormalizing the others.
+    pub fn to_weights4(&self) -> Vec<[f32; 4]> {
+        let mut weights = Vec::with_capacity(self.get_vertex_count());
+        for i in 0..self.get_vertex_count() {
+            let mut packed = [0.0; 4];
+            for (dst, src) in packed.iter_mut().zip(self.get_weights(i)) {
+                *dst = *src;
+            }
+            weights.push(packed);
+        }
+        weights
+    }
+}
+
+/// The reasons the bones of a mesh can't be packed into a `Skin`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SkinError {
+    /// The mesh has more bones than 16 bit joint indices can address.
+    TooManyBones(usize),
+
+    /// The bone with the given name refers to a vertex the mesh doesn't
+    /// have.
+    InvalidVertex(String, u32),
+}
+
+impl fmt::Display for SkinError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SkinError::TooManyBones(count) =>
+                write!(f, "skin error: {} bones do not fit 16 bit joint \
+                           indices", count),
+            SkinError::InvalidVertex(ref bone, vertex) =>
+                write!(f, "skin error: bone {:?} refers to missing vertex {}",
+                       bone, vertex),
+        }
+    }
+}
+
+impl Error for SkinError {}
+
+/// Packs the bone weights of meshes into per-vertex influences.
+pub struct SkinBuilder {
+    max_influences: usize,
+    normalize: bool,
+}
+
+impl SkinBuilder {
+    /// Create a builder keeping four influences per vertex and
+    /// renormalizing the weights.
+    pub fn new() -> SkinBuilder {
+        SkinBuilder {
+            max_influences: 4,
+            normalize: true,
+        }
+    }
+
+    /// Set the number of influences kept per vertex.
+    pub fn set_max_influences(&mut self, max_influences: usize) {
+        self.max_influences = max_influences;
+    }
+
+    /// Choose whether the weights kept for a vertex are scaled to sum up
+    /// to 1.
+    ///
+    /// Dropping the weakest influences of a vertex leaves it with a total
+    /// weight below 1, which shrinks it towards the origin when skinned.
+    pub fn set_normalize(&mut self, normalize: bool) {
+        self.normalize = normalize;
+    }
+
+    /// Pack the bone weights of `mesh`.
+    ///
+    /// Influences with a weight of 0 or less are ignored. If a vertex has
+    /// more influences than allowed, the strongest ones are kept. Ties are
+    /// broken in favour of the bone listed first, so the result is stable.
+    pub fn build(&self, mesh: &Mesh) -> Result<Skin, SkinError> {
+        let bones = mesh.get_bones();
+        if bones.len() > 0x10000 {
+            return Err(SkinError::TooManyBones(bones.len()));
+        }
+
+        let count = mesh.num_vertices as usize;
+        let mut influences: Vec<Vec<(u16, f32)>> = vec![Vec::new(); count];
+        for (index, bone) in bones.iter().enumerate() {
+            for weight in bone.get_weights() {
+                let vertex = weight.vertex_id;
+                let list = match influences.get_mut(vertex as usize) {
+                    Some(list) => list,
+                    None => return Err(SkinError::InvalidVertex(
+                        bone.name.to_string(), vertex)),
+                };
+                // This also drops NaN weights, so they can be sorted below
+                if weight.weight > 0.0 {
+                    list.push((index as u16, weight.weight));
+                }
+            }
+        }
+
+        let n = self.max_influences;
+        let mut skin = Skin {
+            bones: bones.iter().map(|bone| SkinBone {
+                name: bone.name.to_string(),
+                offset_matrix: bone.offset_matrix,
+            }).collect(),
+            max_influences: n,
+            joints: Vec::with_capacity(count * n),
+            weights: Vec::with_capacity(count * n),
+            truncated_vertices: 0,
+            unweighted_vertices: 0,
+        };
+
+        for mut list in influences {
+            if list.is_empty() {
+                skin.unweighted_vertices += 1;
+            }
+            if list.len() > n {
+                skin.truncated_vertices += 1;
+            }
+            // The sort is stable, keeping bones with equal weights in order
+            list.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+            list.truncate(n);
+
+            let total: f32 = list.iter().map(|&(_, weight)| weight).sum();
+            let total = match self.normalize && total > 0.0 {
+                true => total,
+                false => 1.0,
+            };
+            for i in 0..n {
+                let (joint, weight) = list.get(i).cloned().unwrap_or((0, 0.0));
+                skin.joints.push(joint);
+                skin.weights.push(weight / total);
+            }
+        }
+        Ok(skin)
+    }
+}
+
+/// A joint of a `Skeleton`.
+#[derive(Clone)]
+pub struct Joint<'a> {
+    /// The node the joint is attached to
+    pub node: &'a Node,
+
+    /// The index of the parent joint, `None` for root joints
+    pub parent: Option<usize>,
+
+    /// The index of the bone attached to this joint in `Mesh::get_bones`,
+    /// `None` for nodes between the bones and the armature root
+    pub bone: Option<usize>,
+
+    /// The matrix transforming from mesh space to joint space in bind pose.
+    ///
+    /// This is the `Bone::offset_matrix` for joints with a bone. For other
+    /// joints it is the inverse world transform of the node, which matches
+    /// as long as the mesh is not transformed itself.
+    pub inverse_bind_matrix: Matrix4x4,
+}
+
+/// The skeleton deforming a mesh, as a flat array of joints.
+///
+/// Joints are ordered parent before child, so world transforms can be
+/// computed in a single pass over the array.
+#[derive(Clone)]
+pub struct Skeleton<'a> {
+    /// The joints, parents before their children
+    pub joints: Vec<Joint<'a>>,
+
+    /// The index of the joint of each bone of the mesh, in the order of
+    /// `Mesh::get_bones`. `None` for bones without a node.
+    pub bone_joints: Vec<Option<usize>>,
+
+    /// The world transform of the armature root, the node the root joints
+    /// are attached to. Identity if the root joint is the root node.
+    pub root_transform: Matrix4x4,
+
+    /// The names of the bones which don't match any node
+    pub unmatched_bones: Vec<String>,
+}
+
+impl<'a> Skeleton<'a> {
+    /// Build the skeleton of `mesh` from the node hierarchy of `scene`.
+    ///
+    /// The joints are the nodes named after the bones of the mesh, plus all
+    /// nodes between them and their closest common ancestor. That ancestor
+    /// is the armature root. It is only a joint itself if it has a bone.
+    pub fn new(scene: &'a Scene, mesh: &Mesh) -> Skeleton<'a> {
+        let bones = mesh.get_bones();
+        let mut skeleton = Skeleton {
+            joints: Vec::new(),
+            bone_joints: vec![None; bones.len()],
+            root_transform: Matrix4x4::identity(),
+            unmatched_bones: Vec::new(),
+        };
+
+        let mut bone_nodes = HashMap::new();
+        let mut paths: Vec<Vec<&Node>> = Vec::new();
+        for (index, bone) in bones.iter().enumerate() {
+            let name = bone.name.to_string();
+            match scene.find_node(&name) {
+                Some(node) => {
+                    let _ = bone_nodes.insert(node as *const Node, index);
+                    paths.push(node.get_path());
+                }
+                None => skeleton.unmatched_bones.push(name),
+            }
+        }
+        if paths.is_empty() {
+            return skeleton;
+        }
+
+        // The length of the path to the closest common ancestor
+        let mut common = paths[0].len();
+        for path in paths.iter().skip(1) {
+            common = paths[0].iter().zip(path.iter())
+                .take(common)
+                .take_while(|&(a, b)| *a as *const Node == *b as *const Node)
+                .count();
+        }
+        let ancestor = paths[0][common - 1];
+        let root_joint = bone_nodes.contains_key(&(ancestor as *const Node));
+
+        let mut members = HashSet::new();
+        for path in paths.iter() {
+            let start = if root_joint { common - 1 } else { common };
+            for node in path[start..].iter() {
+                let _ = members.insert(*node as *const Node);
+            }
+        }
+
+        skeleton.root_transform = match (root_joint, ancestor.get_parent()) {
+            (false, _) => ancestor.get_world_transform(),
+            (true, Some(parent)) => parent.get_world_transform(),
+            (true, None) => Matrix4x4::identity(),
+        };
+
+        let mut indices = HashMap::new();
+        for visit in ancestor.iter_depth_first() {
+            let node = visit.node;
+            let ptr = node as *const Node;
+            if !members.contains(&ptr) {
+                continue;
+            }
+            let parent = node.get_parent().and_then(|parent| {
+                indices.get(&(parent as *const Node)).cloned()
+            });
+            let bone = bone_nodes.get(&ptr).cloned();
+            let inverse_bind_matrix = match bone {
+                Some(index) => bones[index].offset_matrix,
+                None => node.get_world_transform().inverse(),
+            };
+            let index = skeleton.joints.len();
+            let _ = indices.insert(ptr, index);
+            if let Some(bone) = bone {
+                skeleton.bone_joints[bone] = Some(index);
+            }
+            skeleton.joints.push(Joint {
+                node: node,
+                parent: parent,
+                bone: bone,
+                inverse_bind_matrix: inverse_bind_matrix,
+            });
+        }
+        skeleton
+    }
+
+    /// Find the index of the joint attached to the node named `name`.
+    pub fn find_joint(&self, name: &str) -> Option<usize> {
+        self.joints.iter()
+            .position(|joint| joint.node.name.as_str().ok() == Some(name))
+    }
+
+    /// Compute the world transform of every joint from the given local
+    /// transforms, one per joint, e.g. sampled from an animation.
+    pub fn get_world_transforms(&self, local: &[Matrix4x4]) -> Vec<Matrix4x4> {
+        let mut world: Vec<Matrix4x4> = Vec::with_capacity(self.joints.len());
+        for (joint, local) in self.joints.iter().zip(local.iter()) {
+            let parent = match joint.parent {
+                Some(parent) => world[parent],
+                None => self.root_transform,
+            };
+            world.push(parent * *local);
+        }
+        world
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SkinBuilder, Skeleton};
+    use importer::Importer;
+
+    #[test]
+    fn test_skin() {
+        let importer = Importer::new();
+        let scene = importer
+            .import_from_file("examples/assets/guard-md5/guard.md5mesh")
+            .unwrap();
+        let mesh = scene.get_meshes().iter()
+            .find(|mesh| mesh.num_bones > 0)
+            .unwrap();
+
+        let mut builder = SkinBuilder::new();
+        let skin = builder.build(mesh).unwrap();
+        assert_eq!(skin.bones.len(), mesh.num_bones as usize);
+        assert_eq!(skin.get_vertex_count(), mesh.num_vertices as usize);
+        assert_eq!(skin.to_joints4().len(), mesh.num_vertices as usize);
+        for i in 0..skin.get_vertex_count() {
+            let weights = skin.get_weights(i);
+            let total: f32 = weights.iter().sum();
+            assert!(total == 0.0 || (total - 1.0).abs() < 1e-4);
+            assert!(weights.windows(2).all(|w| w[0] >= w[1]));
+            assert!(skin.get_joints(i).iter()
+                        .all(|&joint| (joint as usize) < skin.bones.len()));
+        }
+
+        builder.set_max_influences(1);
+        let single = builder.build(mesh).unwrap();
+        assert_eq!(single.joints.len(), mesh.num_vertices as usize);
+        assert!(single.truncated_vertices >= skin.truncated_vertices);
+        assert!(single.weights.iter().all(|&w| w == 0.0 || w == 1.0));
+    }
+
+    #[test]
+    fn test_skeleton() {
+        let importer = Importer::new();
+        let scene = importer
+            .import_from_file("examples/assets/guard-md5/guard.md5mesh")
+            .unwrap();
+        let mesh = scene.get_meshes().iter()
+            .find(|mesh| mesh.num_bones > 0)
+            .unwrap();
+
+        let skeleton = Skeleton::new(&scene, mesh);
+        assert!(skeleton.unmatched_bones.is_empty());
+        assert!(skeleton.joints.len() >= mesh.num_bones as usize);
+        assert!(skeleton.bone_joints.iter().all(|joint| joint.is_some()));
+        for (i, joint) in skeleton.joints.iter().enumerate() {
+            assert!(joint.parent.map_or(true, |parent| parent < i));
+        }
+        for (bone, joint) in mesh.get_bones().iter()
+                                 .zip(skeleton.bone_joints.iter()) {
+            let joint = &skeleton.joints[joint.unwrap()];
+            assert_eq!(joint.node.name, bone.name);
+            assert_eq!(joint.inverse_bind_matrix, bone.offset_matrix);
+        }
+
+        let name = mesh.get_bones()[0].name.to_string();
+        assert_eq!(skeleton.find_joint(&name), skeleton.bone_joints[0]);
+    }
+}
+
+// vim: et tw=78 sw=4:
@@ -6,6 +6,15 @@
 //! such as PNG or TGA.
 
 use libc::{c_uchar, c_char, c_uint};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str;
+
+#[cfg(feature = "image")]
+use image::{self, ImageFormat, RgbaImage};
+
+use material::TextureType;
 
 use util::{ptr_to_slice};
 
@@ -20,6 +29,8 @@ use util::{ptr_to_slice};
 // #	define AI_MAKE_EMBEDDED_TEXNAME(_n_) "*" # _n_
 // #endif
 
+/// The size of `Texture::format_hint`, including the terminating zero
+const HINTMAXTEXTURELEN: usize = 9;
 
 /// Helper structure to represent a texel in a ARGB8888 format
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -65,14 +76,14 @@ pub struct Texture {
     /// to determine the type of embedded compressed textures.
     ///
     /// If height != 0 this member is undefined. Otherwise it
-    /// is set set to '\\0\\0\\0\\0' if the loader has no additional
+    /// is set set to all zeros if the loader has no additional
     /// information about the texture file format used OR the
     /// file extension of the format without a trailing dot. If there
     /// are multiple file extensions for a format, the shortest
     /// extension is chosen (JPEG maps to 'jpg', not to 'jpeg').
     /// E.g. 'dds\\0', 'pcx\\0', 'jpg\\0'.  All characters are lower-case.
-    /// The fourth character will always be '\\0'.
-    pub format_hint: [c_char; 4],
+    /// The last character will always be '\\0'.
+    pub format_hint: [c_char; HINTMAXTEXTURELEN],
 
     /// Data of the texture.
     ///
@@ -128,4 +139,360 @@ impl Texture {
             }
         }
     }
+
+    /// Get the file extension of the format of a compressed texture, e.g.
+    /// `"png"` or `"jpg"`.
+    ///
+    /// Returns `None` for uncompressed textures, or if the loader doesn't
+    /// know the format.
+    pub fn get_format_hint(&self) -> Option<&str> {
+        if self.height != 0 {
+            return None;
+        }
+        let hint = unsafe {
+            &*(&self.format_hint as *const [c_char; HINTMAXTEXTURELEN]
+                                 as *const [u8; HINTMAXTEXTURELEN])
+        };
+        let len = hint.iter().position(|&c| c == 0).unwrap_or(hint.len());
+        match str::from_utf8(&hint[..len]) {
+            Ok(hint) if !hint.is_empty() => Some(hint),
+            _ => None,
+        }
+    }
+
+    /// Get a file extension suitable for `save`.
+    ///
+    /// This is the format hint for compressed textures, `"bin"` for
+    /// compressed textures in an unknown format, and `"png"` for
+    /// uncompressed textures. Hints which are not plain ASCII letters and
+    /// digits are not trusted as extensions and also give `"bin"`.
+    pub fn get_file_extension(&self) -> &str {
+        match self.get_format_hint() {
+            Some(hint) if is_plain_extension(hint) => hint,
+            _ if self.height == 0 => "bin",
+            _ => "png",
+        }
+    }
+
+    /// Write the texture to the file at `path`.
+    ///
+    /// Compressed textures are written exactly as they were embedded, so
+    /// `path` should have the extension returned by `get_format_hint`.
+    /// Uncompressed textures are encoded in the format given by the
+    /// extension of `path`, which requires the `image` feature.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        match self.get_texture_data() {
+            TextureData::Encoded { data, .. } => fs::write(path, data),
+            TextureData::Decoded { .. } => self.save_decoded(path.as_ref()),
+        }
+    }
+
+    #[cfg(feature = "image")]
+    fn save_decoded(&self, path: &Path) -> io::Result<()> {
+        self.to_rgba8()
+            .and_then(|image| image.save(path))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+    }
+
+    #[cfg(not(feature = "image"))]
+    fn save_decoded(&self, _path: &Path) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::InvalidInput,
+                           "saving uncompressed textures requires the \
+                            `image` feature"))
+    }
+
+    /// Decode the texture into an RGBA image with 8 bits per channel.
+    ///
+    /// Compressed textures are decoded using the format hint, falling back
+    /// to guessing the format from the data.
+    #[cfg(feature = "image")]
+    pub fn to_rgba8(&self) -> image::ImageResult<RgbaImage> {
+        match self.get_texture_data() {
+            TextureData::Encoded { data, .. } => {
+                let format = self.get_format_hint()
+                                 .and_then(ImageFormat::from_extension);
+                let image = match format {
+                    Some(format) =>
+                        image::load_from_memory_with_format(data, format)
+                            .or_else(|_| image::load_from_memory(data))?,
+                    None => image::load_from_memory(data)?,
+                };
+                Ok(image.to_rgba8())
+            }
+            TextureData::Decoded { width, height, data } => {
+                let mut pixels = Vec::with_capacity(data.len() * 4);
+                for texel in data {
+                    pixels.extend_from_slice(&[texel.r, texel.g,
+                                               texel.b, texel.a]);
+                }
+                Ok(RgbaImage::from_raw(width, height, pixels)
+                       .expect("texel count matches the texture size"))
+            }
+        }
+    }
+}
+
+/// Where the image referenced by a material can be found.
+///
+/// Returned by `Scene::resolve_texture`.
+pub enum TextureSource<'a> {
+    /// An image file which exists on disk
+    File(PathBuf),
+
+    /// A texture embedded into the model file, the index is its position in
+    /// `Scene::get_textures`
+    Embedded(usize, &'a Texture),
+}
+
+/// The files written by `Scene::extract_textures`.
+#[derive(Debug)]
+pub struct ExtractedTextures {
+    /// The file written for each embedded texture, in the order of
+    /// `Scene::get_textures`. `None` for the textures listed in `failures`.
+    pub files: Vec<Option<PathBuf>>,
+
+    /// Every material texture referencing an embedded texture, with the
+    /// path of the file it was written to. Textures which could not be
+    /// written are left out.
+    pub references: Vec<ExtractedTextureRef>,
+
+    /// The index of every embedded texture which could not be written, with
+    /// the reason
+    pub failures: Vec<(usize, io::Error)>,
+}
+
+/// A material texture referencing an extracted embedded texture.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExtractedTextureRef {
+    /// The index of the material in `Scene::get_materials`
+    pub material: usize,
+
+    /// The type of the texture
+    pub tex_type: TextureType,
+
+    /// The index of the texture within its type, as passed to
+    /// `Material::get_texture`
+    pub index: usize,
+
+    /// The index of the embedded texture in `Scene::get_textures`
+    pub embedded: usize,
+
+    /// The path of the extracted file, replacing the `"*<index>"` path of
+    /// the texture
+    pub path: PathBuf,
+}
+
+/// Check that `ext` can be used as a file extension as it is, without
+/// introducing separators or other special characters into a path.
+fn is_plain_extension(ext: &str) -> bool {
+    !ext.is_empty() && ext.bytes().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Get the index of an embedded texture from a texture path of the form
+/// `"*<index>"`.
+///
+/// Returns `None` if `path` doesn't reference an embedded texture.
+pub fn embedded_texture_index(path: &str) -> Option<usize> {
+    if path.starts_with('*') {
+        path[1..].parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Find the image file for the texture path `reference` of the model loaded
+/// from `model_path`.
+///
+/// Texture paths are often written on another machine, so the following
+/// locations are tried in order:
+///
+/// 1. `reference` itself, if it is an absolute path.
+/// 2. `reference` relative to the directory of the model, after converting
+///    `\` to `/`.
+/// 3. The same path, matching every component case insensitively.
+/// 4. A file with the same name, compared case insensitively, in the model
+///    directory or up to two levels of subdirectories below it.
+///
+/// Returns `None` if no existing file was found.
+pub fn find_texture_file<P: AsRef<Path>>(model_path: P, reference: &str)
+                                         -> Option<PathBuf> {
+    let reference = reference.trim().replace('\\', "/");
+    if reference.is_empty() {
+        return None;
+    }
+    let base_dir = model_path.as_ref().parent().unwrap_or(Path::new(""));
+    let base_dir = if base_dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        base_dir
+    };
+
+    let path = Path::new(&reference);
+    if path.is_absolute() && path.is_file() {
+        return Some(path.to_path_buf());
+    }
+
+    let relative = reference.trim_start_matches('/');
+    let candidate = base_dir.join(relative);
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+
+    if let Some(found) = find_case_insensitive(base_dir, relative) {
+        return Some(found);
+    }
+
+    let file_name = match relative.rsplit('/').next() {
+        Some(name) if !name.is_empty() => name,
+        _ => return None,
+    };
+    find_by_name(base_dir, file_name, 2)
+}
+
+/// Follow `relative` from `dir`, matching each component case
+/// insensitively.
+fn find_case_insensitive(dir: &Path, relative: &str) -> Option<PathBuf> {
+    let mut current = dir.to_path_buf();
+    let components = relative.split('/').filter(|c| !c.is_empty() && *c != ".");
+    for component in components {
+        if component == ".." {
+            current.push("..");
+            continue;
+        }
+        current = fs::read_dir(&current).ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .find(|path| names_match(path, component))?;
+    }
+    if current.is_file() { Some(current) } else { None }
+}
+
+/// Search `dir` and up to `depth` levels of subdirectories for a file
+/// named `name`, compared case insensitively.
+fn find_by_name(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+    let mut subdirs = Vec::new();
+    for entry in fs::read_dir(dir).ok()?.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirs.push(path);
+        } else if names_match(&path, name) {
+            return Some(path);
+        }
+    }
+    if depth == 0 {
+        return None;
+    }
+    subdirs.sort();
+    subdirs.iter().filter_map(|sub| find_by_name(sub, name, depth - 1)).next()
+}
+
+fn names_match(path: &Path, name: &str) -> bool {
+    match path.file_name().and_then(|n| n.to_str()) {
+        Some(file_name) => file_name.eq_ignore_ascii_case(name),
+        None => false,
+    }
 }
+
+#[cfg(test)]
+mod test {
+    use libc::c_char;
+    use std::path::PathBuf;
+
+    use super::{embedded_texture_index, find_texture_file,
+                is_plain_extension};
+    use super::{Texel, Texture, HINTMAXTEXTURELEN};
+
+    const MODEL: &'static str = "examples/assets/guard-md5/guard.md5mesh";
+
+    fn hint(ext: &[u8]) -> [c_char; HINTMAXTEXTURELEN] {
+        let mut hint = [0; HINTMAXTEXTURELEN];
+        for (dst, src) in hint.iter_mut().zip(ext) {
+            *dst = *src as c_char;
+        }
+        hint
+    }
+
+    #[test]
+    fn test_embedded_texture_index() {
+        assert_eq!(embedded_texture_index("*0"), Some(0));
+        assert_eq!(embedded_texture_index("*12"), Some(12));
+        assert_eq!(embedded_texture_index("*x"), None);
+        assert_eq!(embedded_texture_index("tex.png"), None);
+    }
+
+    #[test]
+    fn test_find_texture_file() {
+        let expected = Some(PathBuf::from(
+            "examples/assets/guard-md5/guard_body.tga"));
+        let found = |reference| find_texture_file(MODEL, reference);
+
+        assert_eq!(found("guard_body.tga"), expected);
+        assert_eq!(found("./GUARD_BODY.TGA"), expected);
+        assert_eq!(found("C:\\Models\\textures\\guard_body.tga"), expected);
+        assert_eq!(found("missing.tga"), None);
+        assert_eq!(found(""), None);
+    }
+
+    #[test]
+    fn test_format_hint() {
+        let mut data = *b"not really a png";
+        let tex = Texture {
+            width: data.len() as u32,
+            height: 0,
+            format_hint: hint(b"png"),
+            pc_data: data.as_mut_ptr() as *mut Texel,
+        };
+        assert_eq!(tex.get_format_hint(), Some("png"));
+
+        assert_eq!(tex.get_file_extension(), "png");
+
+        let tex = Texture { format_hint: hint(b"a/b"), .. tex };
+        assert_eq!(tex.get_format_hint(), Some("a/b"));
+        assert_eq!(tex.get_file_extension(), "bin");
+
+        let tex = Texture { format_hint: [0; HINTMAXTEXTURELEN], .. tex };
+        assert_eq!(tex.get_format_hint(), None);
+        assert_eq!(tex.get_file_extension(), "bin");
+    }
+
+    #[test]
+    fn test_plain_extension() {
+        assert!(is_plain_extension("png"));
+        assert!(is_plain_extension("jp2"));
+        assert!(!is_plain_extension(""));
+        assert!(!is_plain_extension(".."));
+        assert!(!is_plain_extension("a\\b"));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_to_rgba8() {
+        use std::io::Cursor;
+        use image::{ImageFormat, Rgba, RgbaImage};
+
+        let mut texels = [Texel { b: 1, g: 2, r: 3, a: 4 },
+                          Texel { b: 5, g: 6, r: 7, a: 8 }];
+        let tex = Texture {
+            width: 2,
+            height: 1,
+            format_hint: [0; HINTMAXTEXTURELEN],
+            pc_data: texels.as_mut_ptr(),
+        };
+        let image = tex.to_rgba8().unwrap();
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(1, 0), &Rgba([7, 6, 5, 8]));
+
+        let mut png = Vec::new();
+        image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).unwrap();
+        let tex = Texture {
+            width: png.len() as u32,
+            height: 0,
+            format_hint: hint(b"png"),
+            pc_data: png.as_mut_ptr() as *mut Texel,
+        };
+        let decoded: RgbaImage = tex.to_rgba8().unwrap();
+        assert_eq!(decoded, image);
+    }
+}
+
+// vim: et tw=78 sw=4:
@@ -2,7 +2,7 @@
 
 use std::str;
 use std::fmt;
-use libc::{c_float, size_t, c_uchar, c_uint};
+use libc::{c_float, c_uchar, c_uint};
 use std::ops::{Add, Div, Mul, Sub};
 
 use vecmath as m;
@@ -154,7 +154,9 @@ pub struct AiString {
     /// Binary length of the string excluding the terminal 0. This is NOT the
     /// logical length of strings containing UTF-8 multibyte sequences! It's
     /// the number of bytes from the beginning of the string to its end.
-    length: size_t,
+    ///
+    /// Assimp 3.x stored this as a `size_t`, since assimp 4.0 it has 32 bits.
+    length: c_uint,
 
     /// String buffer. Size limit is MAXLEN
     data: [c_uchar; MAXLEN],
@@ -170,18 +172,26 @@ impl AiString {
     }
 
     /// Get a `str` representation of this `AiString`
+    ///
+    /// Only the first `length` bytes are used, anything assimp left in the
+    /// buffer after them is ignored.
     pub fn as_str(&self) -> Result<&str, str::Utf8Error> {
-        str::from_utf8(&(self.data))
+        str::from_utf8(self.as_bytes())
     }
 
     /// Get a `String` representation of this `AiString`
     pub fn into_string(&self) -> Option<String> {
-        match String::from_utf8((self.data)
-                                .to_vec()) {
+        match String::from_utf8(self.as_bytes().to_vec()) {
             Err(_) => None,
             Ok(s) => Some(s),
         }
     }
+
+    /// Get the bytes of this `AiString`, excluding the terminating zero
+    pub fn as_bytes(&self) -> &[u8] {
+        let len = self.length as usize;
+        &self.data[.. if len < MAXLEN { len } else { MAXLEN }]
+    }
 }
 
 impl fmt::Debug for AiString {
@@ -703,6 +713,28 @@ impl Matrix4x4 {
             d1:  inv[3][0], d2: inv[3][1], d3: inv[3][2], d4: inv[3][3],
         }
     }
+
+    /// Transform a point by this matrix, including its translation
+    pub fn transform_point(&self, p: &Vector3D) -> Vector3D {
+        let (x, y, z) = (p.x, p.y, p.z);
+        let w = self.d1 * x + self.d2 * y + self.d3 * z + self.d4;
+        let w = if w != 0.0 { w } else { 1.0 };
+        Vector3D {
+            x: (self.a1 * x + self.a2 * y + self.a3 * z + self.a4) / w,
+            y: (self.b1 * x + self.b2 * y + self.b3 * z + self.b4) / w,
+            z: (self.c1 * x + self.c2 * y + self.c3 * z + self.c4) / w,
+        }
+    }
+
+    /// Transform a direction by this matrix, ignoring its translation
+    pub fn transform_vector(&self, v: &Vector3D) -> Vector3D {
+        let (x, y, z) = (v.x, v.y, v.z);
+        Vector3D {
+            x: self.a1 * x + self.a2 * y + self.a3 * z,
+            y: self.b1 * x + self.b2 * y + self.b3 * z,
+            z: self.c1 * x + self.c2 * y + self.c3 * z,
+        }
+    }
 }
 
 impl Mul for Matrix4x4 {
@@ -717,11 +749,205 @@ impl Mul for Matrix4x4 {
     }
 }
 
-// #[cfg(test)]
-// mod test {
-//     use super::Matrix4x4;
-//     #[test]
-//     fn test_inv() {
-//         Matrix4x4::identity() * 
-//     }
-// }
+/// An axis-aligned bounding box.
+///
+/// A box without any points is empty, its `min` is larger than its `max` in
+/// every dimension.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Aabb {
+    /// The corner with the smallest coordinates
+    pub min: Vector3D,
+    /// The corner with the largest coordinates
+    pub max: Vector3D,
+}
+
+impl Aabb {
+    /// Create an empty bounding box
+    pub fn empty() -> Aabb {
+        let inf = ::std::f32::INFINITY;
+        Aabb {
+            min: Vector3D { x: inf, y: inf, z: inf },
+            max: Vector3D { x: -inf, y: -inf, z: -inf },
+        }
+    }
+
+    /// Create the smallest bounding box containing all `points`
+    pub fn from_points(points: &[Vector3D]) -> Aabb {
+        let mut aabb = Aabb::empty();
+        for p in points.iter() {
+            aabb.add_point(p);
+        }
+        aabb
+    }
+
+    /// Check whether the box contains no points at all
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y ||
+        self.min.z > self.max.z
+    }
+
+    /// Grow the box to contain `p`
+    pub fn add_point(&mut self, p: &Vector3D) {
+        self.min = Vector3D {
+            x: self.min.x.min(p.x),
+            y: self.min.y.min(p.y),
+            z: self.min.z.min(p.z),
+        };
+        self.max = Vector3D {
+            x: self.max.x.max(p.x),
+            y: self.max.y.max(p.y),
+            z: self.max.z.max(p.z),
+        };
+    }
+
+    /// Get the smallest box containing both this box and `other`
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        let mut aabb = *self;
+        if !other.is_empty() {
+            aabb.add_point(&other.min);
+            aabb.add_point(&other.max);
+        }
+        aabb
+    }
+
+    /// Check whether `p` lies inside the box or on its boundary
+    pub fn contains(&self, p: &Vector3D) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x &&
+        self.min.y <= p.y && p.y <= self.max.y &&
+        self.min.z <= p.z && p.z <= self.max.z
+    }
+
+    /// Get the center of the box
+    pub fn center(&self) -> Vector3D {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Get the size of the box along each axis
+    pub fn size(&self) -> Vector3D {
+        self.max - self.min
+    }
+
+    /// Get the eight corners of the box
+    pub fn get_corners(&self) -> [Vector3D; 8] {
+        let (min, max) = (self.min, self.max);
+        let corner = |x: bool, y: bool, z: bool| Vector3D {
+            x: if x { max.x } else { min.x },
+            y: if y { max.y } else { min.y },
+            z: if z { max.z } else { min.z },
+        };
+        [
+            corner(false, false, false), corner(true, false, false),
+            corner(false, true, false), corner(true, true, false),
+            corner(false, false, true), corner(true, false, true),
+            corner(false, true, true), corner(true, true, true),
+        ]
+    }
+
+    /// Get the bounding box of this box transformed by `m`
+    ///
+    /// The result contains the whole transformed box, so it is usually
+    /// larger than the bounding box of the transformed points.
+    pub fn transform(&self, m: &Matrix4x4) -> Aabb {
+        if self.is_empty() {
+            return *self;
+        }
+        let mut aabb = Aabb::empty();
+        for corner in self.get_corners().iter() {
+            aabb.add_point(&m.transform_point(corner));
+        }
+        aabb
+    }
+}
+
+/// A bounding sphere.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BoundingSphere {
+    /// The center of the sphere
+    pub center: Vector3D,
+    /// The radius of the sphere, negative for a sphere without any points
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Create a sphere containing all `points`
+    ///
+    /// The sphere is centered on the bounding box of the points. This is
+    /// fast, but not necessarily the smallest sphere possible.
+    pub fn from_points(points: &[Vector3D]) -> BoundingSphere {
+        let center = match points.is_empty() {
+            true => Vector3D { x: 0.0, y: 0.0, z: 0.0 },
+            false => Aabb::from_points(points).center(),
+        };
+        let radius = points.iter()
+            .map(|p| (*p - center).norm())
+            .fold(-1.0, f32::max);
+        BoundingSphere {
+            center: center,
+            radius: radius,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem;
+
+    use super::{AiString, MAXLEN};
+    use super::{Aabb, BoundingSphere, Matrix4x4, Vector3D};
+
+    fn ai_string(data: &[u8], length: usize) -> AiString {
+        let mut s = AiString::new();
+        s.data[..data.len()].copy_from_slice(data);
+        s.length = length as u32;
+        s
+    }
+
+    #[test]
+    fn test_ai_string_length() {
+        let s = ai_string(b"mtl\0garbage", 3);
+        assert_eq!(s.as_bytes(), b"mtl");
+        assert_eq!(s.as_str(), Ok("mtl"));
+        assert_eq!(s.into_string(), Some("mtl".to_string()));
+        assert_eq!(AiString::new().as_str(), Ok(""));
+
+        let s = ai_string(b"abc", MAXLEN + 10);
+        assert_eq!(s.as_bytes().len(), MAXLEN);
+
+        // The assimp 4.x layout, a 32 bit length followed by the data
+        assert_eq!(mem::size_of::<AiString>(), 4 + MAXLEN);
+    }
+
+    fn v(x: f32, y: f32, z: f32) -> Vector3D {
+        Vector3D { x: x, y: y, z: z }
+    }
+
+    #[test]
+    fn test_transform_point() {
+        let m = v(1.0, 2.0, 3.0).translation_matrix() *
+                v(2.0, 2.0, 2.0).scaling_matrix();
+        assert_eq!(m.transform_point(&v(1.0, 1.0, 1.0)), v(3.0, 4.0, 5.0));
+        assert_eq!(m.transform_vector(&v(1.0, 1.0, 1.0)), v(2.0, 2.0, 2.0));
+        assert_eq!(Matrix4x4::identity().transform_point(&v(1.0, 2.0, 3.0)),
+                   v(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let points = [v(-1.0, 0.0, 2.0), v(1.0, 4.0, 2.0), v(0.0, 2.0, -2.0)];
+        let aabb = Aabb::from_points(&points);
+        assert_eq!(aabb.min, v(-1.0, 0.0, -2.0));
+        assert_eq!(aabb.max, v(1.0, 4.0, 2.0));
+        assert_eq!(aabb.center(), v(0.0, 2.0, 0.0));
+        assert!(aabb.contains(&v(0.5, 0.5, 0.5)));
+        assert!(Aabb::empty().is_empty());
+        assert_eq!(Aabb::empty().union(&aabb), aabb);
+
+        let moved = aabb.transform(&v(1.0, 0.0, 0.0).translation_matrix());
+        assert_eq!(moved.min, v(0.0, 0.0, -2.0));
+
+        let sphere = BoundingSphere::from_points(&points);
+        assert_eq!(sphere.center, v(0.0, 2.0, 0.0));
+        assert!(points.iter().all(|p| (*p - sphere.center).norm()
+                                      <= sphere.radius));
+    }
+}
@@ -1,12 +1,35 @@
 use std::slice;
 use std::mem;
 
+// Assimp leaves array pointers null when the array is empty, which
+// `slice::from_raw_parts` doesn't allow even for a length of 0.
+
 #[inline(always)]
 pub unsafe fn ptr_ptr_to_slice<'a, T>(ptr: *mut*mut T, len: usize) -> &'a [&'a T] {
+    if ptr.is_null() {
+        return &[];
+    }
     mem::transmute(slice::from_raw_parts(ptr, len as usize))
 }
 
 #[inline(always)]
 pub unsafe fn ptr_to_slice<'a, T>(ptr: *mut T, len: usize) -> &'a [T] {
+    if ptr.is_null() {
+        return &[];
+    }
     slice::from_raw_parts(ptr, len)
 }
+
+#[cfg(test)]
+mod test {
+    use std::ptr;
+    use super::{ptr_ptr_to_slice, ptr_to_slice};
+
+    #[test]
+    fn test_null_slices() {
+        unsafe {
+            assert!(ptr_to_slice::<u32>(ptr::null_mut(), 0).is_empty());
+            assert!(ptr_ptr_to_slice::<u32>(ptr::null_mut(), 0).is_empty());
+        }
+    }
+}
@@ -0,0 +1,496 @@
+//! Building interleaved vertex and index buffers from meshes
+//!
+//! A `VertexLayout` describes which attributes of a `Mesh` end up in a
+//! vertex, in which format and at which offset. A `VertexBufferBuilder`
+//! then packs the mesh into a single byte buffer following that layout, plus
+//! an index buffer built from the faces of the mesh. The result can be
+//! uploaded to a graphics API as it is.
+//!
+//! All values are written in little endian byte order.
+
+use std::error::Error;
+use std::fmt;
+
+use mesh::Mesh;
+use types::{Vector3D, Color4D};
+
+/// A per-vertex attribute of a mesh.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum VertexAttribute {
+    /// The vertex position, see `Mesh::get_vertices`
+    Position,
+    /// The vertex normal, see `Mesh::get_normals`
+    Normal,
+    /// The vertex tangent, see `Mesh::get_tangents`
+    Tangent,
+    /// The vertex bitangent, see `Mesh::get_bitangents`
+    Bitangent,
+    /// The texture coordinates of the given UV channel, see
+    /// `Mesh::get_texture_coords`
+    TexCoord(usize),
+    /// The vertex color of the given color set, see `Mesh::get_colors`
+    Color(usize),
+}
+
+impl fmt::Display for VertexAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VertexAttribute::Position => write!(f, "position"),
+            VertexAttribute::Normal => write!(f, "normal"),
+            VertexAttribute::Tangent => write!(f, "tangent"),
+            VertexAttribute::Bitangent => write!(f, "bitangent"),
+            VertexAttribute::TexCoord(i) => write!(f, "texture coords {}", i),
+            VertexAttribute::Color(i) => write!(f, "color {}", i),
+        }
+    }
+}
+
+/// The format an attribute is stored in.
+///
+/// Formats with fewer components than the attribute drop the trailing
+/// components. Formats with more components pad positions with a `w` of 1,
+/// and all other attributes with 0.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum VertexFormat {
+    /// Two 32 bit floats
+    Float32x2,
+    /// Three 32 bit floats
+    Float32x3,
+    /// Four 32 bit floats
+    Float32x4,
+    /// Four unsigned bytes, mapping [0..1] to [0..255]. Values outside of
+    /// [0..1] are clamped.
+    Unorm8x4,
+}
+
+impl VertexFormat {
+    /// Get the size of a value in this format in bytes.
+    pub fn size(&self) -> usize {
+        match *self {
+            VertexFormat::Float32x2 => 8,
+            VertexFormat::Float32x3 => 12,
+            VertexFormat::Float32x4 => 16,
+            VertexFormat::Unorm8x4 => 4,
+        }
+    }
+
+    fn write(&self, value: [f32; 4], out: &mut Vec<u8>) {
+        let count = match *self {
+            VertexFormat::Float32x2 => 2,
+            VertexFormat::Float32x3 => 3,
+            VertexFormat::Float32x4 => 4,
+            VertexFormat::Unorm8x4 => {
+                for component in value.iter() {
+                    let byte = component.max(0.0).min(1.0) * 255.0;
+                    out.push(byte.round() as u8);
+                }
+                return;
+            }
+        };
+        for component in value[..count].iter() {
+            out.extend_from_slice(&component.to_le_bytes());
+        }
+    }
+}
+
+/// A single attribute of a `VertexLayout`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VertexElement {
+    /// The attribute stored
+    pub attribute: VertexAttribute,
+
+    /// The format the attribute is stored in
+    pub format: VertexFormat,
+
+    /// The offset of the attribute from the start of the vertex in bytes
+    pub offset: usize,
+}
+
+/// The layout of a single vertex in an interleaved vertex buffer.
+///
+/// Attributes are packed in the order they are added, without padding:
+///
+/// ```no_run
+/// use assimp::vertex::{VertexLayout, VertexAttribute, VertexFormat};
+///
+/// let layout = VertexLayout::new()
+///     .with(VertexAttribute::Position, VertexFormat::Float32x3)
+///     .with(VertexAttribute::Normal, VertexFormat::Float32x3)
+///     .with(VertexAttribute::TexCoord(0), VertexFormat::Float32x2);
+/// assert_eq!(layout.get_stride(), 32);
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VertexLayout {
+    elements: Vec<VertexElement>,
+    stride: usize,
+}
+
+impl VertexLayout {
+    /// Create an empty layout.
+    pub fn new() -> VertexLayout {
+        VertexLayout {
+            elements: Vec::new(),
+            stride: 0,
+        }
+    }
+
+    /// Append `attribute`, stored as `format`, to the layout.
+    pub fn with(mut self,
+                attribute: VertexAttribute,
+                format: VertexFormat)
+                -> VertexLayout {
+        self.elements.push(VertexElement {
+            attribute: attribute,
+            format: format,
+            offset: self.stride,
+        });
+        self.stride += format.size();
+        self
+    }
+
+    /// Get the attributes of the layout, in the order they are stored.
+    pub fn get_elements(&self) -> &[VertexElement] {
+        &self.elements
+    }
+
+    /// Get the size of a single vertex in bytes.
+    pub fn get_stride(&self) -> usize {
+        self.stride
+    }
+}
+
+/// The type of the indices in an index buffer.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum IndexFormat {
+    /// 16 bit indices, for meshes with at most 65536 vertices
+    U16,
+    /// 32 bit indices
+    U32,
+}
+
+/// The indices of the triangles of a mesh.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum IndexBuffer {
+    /// 16 bit indices
+    U16(Vec<u16>),
+    /// 32 bit indices
+    U32(Vec<u32>),
+}
+
+impl IndexBuffer {
+    /// Get the format of the indices.
+    pub fn format(&self) -> IndexFormat {
+        match *self {
+            IndexBuffer::U16(_) => IndexFormat::U16,
+            IndexBuffer::U32(_) => IndexFormat::U32,
+        }
+    }
+
+    /// Get the number of indices.
+    pub fn len(&self) -> usize {
+        match *self {
+            IndexBuffer::U16(ref indices) => indices.len(),
+            IndexBuffer::U32(ref indices) => indices.len(),
+        }
+    }
+
+    /// Check whether the buffer holds no indices.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the indices as little endian bytes, ready to be uploaded.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            IndexBuffer::U16(ref indices) =>
+                indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+            IndexBuffer::U32(ref indices) =>
+                indices.iter().flat_map(|i| i.to_le_bytes()).collect(),
+        }
+    }
+}
+
+/// A mesh packed into an interleaved vertex buffer and an index buffer.
+#[derive(Clone, PartialEq, Debug)]
+pub struct VertexBuffer {
+    /// The layout of the vertices
+    pub layout: VertexLayout,
+
+    /// The vertex data, `layout.get_stride()` bytes per vertex
+    pub vertices: Vec<u8>,
+
+    /// Three indices per triangle
+    pub indices: IndexBuffer,
+
+    /// The number of faces which were left out because they are not
+    /// triangles. Always 0 unless `VertexBufferBuilder::skip_non_triangles`
+    /// is enabled.
+    pub skipped_faces: usize,
+}
+
+impl VertexBuffer {
+    /// Get the number of vertices in the buffer.
+    pub fn get_vertex_count(&self) -> usize {
+        match self.layout.get_stride() {
+            0 => 0,
+            stride => self.vertices.len() / stride,
+        }
+    }
+}
+
+/// The reasons a mesh can't be packed into a vertex buffer.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum VertexBufferError {
+    /// The layout contains an attribute the mesh does not have.
+    MissingAttribute(VertexAttribute),
+
+    /// The face with the given index is not a triangle. Use
+    /// `Process::Triangulate` when importing, or skip such faces with
+    /// `VertexBufferBuilder::skip_non_triangles`.
+    NonTriangleFace(usize),
+
+    /// 16 bit indices were requested, but the mesh has the given number of
+    /// vertices, more than they can address.
+    TooManyVertices(usize),
+}
+
+impl fmt::Display for VertexBufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VertexBufferError::MissingAttribute(attribute) =>
+                write!(f, "vertex buffer error: mesh has no {}", attribute),
+            VertexBufferError::NonTriangleFace(index) =>
+                write!(f, "vertex buffer error: face {} is not a triangle",
+                       index),
+            VertexBufferError::TooManyVertices(count) =>
+                write!(f, "vertex buffer error: {} vertices do not fit 16 \
+                           bit indices", count),
+        }
+    }
+}
+
+impl Error for VertexBufferError {}
+
+/// Packs meshes into interleaved vertex buffers.
+pub struct VertexBufferBuilder {
+    layout: VertexLayout,
+    index_format: Option<IndexFormat>,
+    skip_non_triangles: bool,
+}
+
+impl VertexBufferBuilder {
+    /// Create a builder for vertex buffers with the given layout.
+    ///
+    /// By default 16 bit indices are used for meshes small enough, and
+    /// faces which are not triangles are rejected.
+    pub fn new(layout: VertexLayout) -> VertexBufferBuilder {
+        VertexBufferBuilder {
+            layout: layout,
+            index_format: None,
+            skip_non_triangles: false,
+        }
+    }
+
+    /// Always use indices of the given format.
+    pub fn set_index_format(&mut self, format: IndexFormat) {
+        self.index_format = Some(format);
+    }
+
+    /// Choose the smallest index format that fits each mesh.
+    pub fn reset_index_format(&mut self) {
+        self.index_format = None;
+    }
+
+    /// Leave out points, lines and polygons, instead of failing on them.
+    ///
+    /// The number of faces left out is reported in
+    /// `VertexBuffer::skipped_faces`.
+    pub fn skip_non_triangles(&mut self, skip: bool) {
+        self.skip_non_triangles = skip;
+    }
+
+    /// Pack `mesh` into a vertex buffer.
+    pub fn build(&self, mesh: &Mesh)
+                 -> Result<VertexBuffer, VertexBufferError> {
+        let vertices = self.build_vertices(mesh)?;
+
+        let mut skipped_faces = 0;
+        let mut triangles = Vec::with_capacity(mesh.num_faces as usize * 3);
+        for (i, face) in mesh.get_faces().iter().enumerate() {
+            let indices = face.get_indices();
+            if indices.len() == 3 {
+                triangles.extend_from_slice(indices);
+            } else if self.skip_non_triangles {
+                skipped_faces += 1;
+            } else {
+                return Err(VertexBufferError::NonTriangleFace(i));
+            }
+        }
+
+        let count = mesh.num_vertices as usize;
+        let format = match self.index_format {
+            Some(format) => format,
+            None if count <= 0x10000 => IndexFormat::U16,
+            None => IndexFormat::U32,
+        };
+        let indices = match format {
+            IndexFormat::U16 if count > 0x10000 =>
+                return Err(VertexBufferError::TooManyVertices(count)),
+            IndexFormat::U16 =>
+                IndexBuffer::U16(triangles.iter().map(|&i| i as u16).collect()),
+            IndexFormat::U32 => IndexBuffer::U32(triangles),
+        };
+
+        Ok(VertexBuffer {
+            layout: self.layout.clone(),
+            vertices: vertices,
+            indices: indices,
+            skipped_faces: skipped_faces,
+        })
+    }
+
+    fn build_vertices(&self, mesh: &Mesh)
+                      -> Result<Vec<u8>, VertexBufferError> {
+        let count = mesh.num_vertices as usize;
+        let mut sources = Vec::with_capacity(self.layout.elements.len());
+        for element in self.layout.get_elements() {
+            let source = AttributeSource::new(mesh, element.attribute, count)
+                .ok_or(VertexBufferError::MissingAttribute(element.attribute))?;
+            sources.push((source, element.format));
+        }
+
+        let mut vertices = Vec::with_capacity(count * self.layout.stride);
+        for i in 0..count {
+            for &(ref source, format) in sources.iter() {
+                format.write(source.get(i), &mut vertices);
+            }
+        }
+        Ok(vertices)
+    }
+}
+
+/// The per-vertex data of a single attribute of a mesh.
+enum AttributeSource<'a> {
+    Point(&'a [Vector3D]),
+    Direction(&'a [Vector3D]),
+    Color(&'a [Color4D]),
+}
+
+impl<'a> AttributeSource<'a> {
+    fn new(mesh: &'a Mesh, attribute: VertexAttribute, count: usize)
+           -> Option<AttributeSource<'a>> {
+        let source = match attribute {
+            VertexAttribute::Position =>
+                AttributeSource::Point(mesh.get_vertices()),
+            VertexAttribute::Normal =>
+                AttributeSource::Direction(mesh.get_normals()),
+            VertexAttribute::Tangent =>
+                AttributeSource::Direction(mesh.get_tangents()),
+            VertexAttribute::Bitangent =>
+                AttributeSource::Direction(mesh.get_bitangents()),
+            VertexAttribute::TexCoord(set) =>
+                AttributeSource::Direction(
+                    mesh.get_texture_coords().get(set).cloned()?),
+            VertexAttribute::Color(set) =>
+                AttributeSource::Color(mesh.get_colors().get(set).cloned()?),
+        };
+        let len = match source {
+            AttributeSource::Point(values) => values.len(),
+            AttributeSource::Direction(values) => values.len(),
+            AttributeSource::Color(values) => values.len(),
+        };
+        match len < count {
+            true => None,
+            false => Some(source),
+        }
+    }
+
+    fn get(&self, i: usize) -> [f32; 4] {
+        match *self {
+            AttributeSource::Point(values) => {
+                let v = values[i];
+                [v.x, v.y, v.z, 1.0]
+            }
+            AttributeSource::Direction(values) => {
+                let v = values[i];
+                [v.x, v.y, v.z, 0.0]
+            }
+            AttributeSource::Color(values) => {
+                let c = values[i];
+                [c.r, c.g, c.b, c.a]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{VertexLayout, VertexAttribute, VertexFormat,
+                VertexBufferBuilder, VertexBufferError, IndexBuffer,
+                IndexFormat};
+    use importer::Importer;
+    use postprocess::Process;
+
+    const QUAD: &'static [u8] = b"v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+f 1/1 2/2 3/3 4/4
+";
+
+    #[test]
+    fn test_vertex_buffer() {
+        let layout = VertexLayout::new()
+            .with(VertexAttribute::Position, VertexFormat::Float32x3)
+            .with(VertexAttribute::TexCoord(0), VertexFormat::Float32x2);
+        assert_eq!(layout.get_stride(), 20);
+        assert_eq!(layout.get_elements()[1].offset, 12);
+
+        let mut importer = Importer::new();
+        let scene = importer.import_from_memory(QUAD, "obj").unwrap();
+        let mesh = scene.get_meshes()[0];
+        assert_eq!(mesh.to_vertex_buffer(&layout),
+                   Err(VertexBufferError::NonTriangleFace(0)));
+
+        let mut builder = VertexBufferBuilder::new(layout.clone());
+        builder.skip_non_triangles(true);
+        let buffer = builder.build(mesh).unwrap();
+        assert_eq!(buffer.skipped_faces, 1);
+        assert!(buffer.indices.is_empty());
+
+        let normals = layout.clone()
+            .with(VertexAttribute::Normal, VertexFormat::Float32x3);
+        assert_eq!(VertexBufferBuilder::new(normals).build(mesh),
+                   Err(VertexBufferError::MissingAttribute(
+                       VertexAttribute::Normal)));
+
+        importer.add_processing_steps(&[Process::Triangulate]);
+        let scene = importer.import_from_memory(QUAD, "obj").unwrap();
+        let mesh = scene.get_meshes()[0];
+        let buffer = mesh.to_vertex_buffer(&layout).unwrap();
+        assert_eq!(buffer.skipped_faces, 0);
+        assert_eq!(buffer.get_vertex_count(), mesh.num_vertices as usize);
+        assert_eq!(buffer.vertices.len(), mesh.num_vertices as usize * 20);
+        assert_eq!(buffer.indices.format(), IndexFormat::U16);
+        assert_eq!(buffer.indices.len(), 6);
+
+        let vertex = &buffer.vertices[20..40];
+        let position = mesh.get_vertices()[1];
+        let x = f32::from_le_bytes([vertex[0], vertex[1], vertex[2],
+                                    vertex[3]]);
+        assert_eq!(x, { position.x });
+
+        builder.set_index_format(IndexFormat::U32);
+        builder.skip_non_triangles(false);
+        match builder.build(mesh).unwrap().indices {
+            IndexBuffer::U32(indices) => assert_eq!(indices.len(), 6),
+            IndexBuffer::U16(_) => panic!("expected 32 bit indices"),
+        }
+    }
+}
+
+// vim: et tw=78 sw=4: